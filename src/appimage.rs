@@ -0,0 +1,589 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use backhand::{FilesystemReader, InnerNode, Node, SquashfsFileReader};
+
+use crate::errors::{self, Error};
+
+/// Version information found in the payload of an AppImage, by where it was found.
+#[derive(Debug, Default)]
+pub struct VersionInfo {
+    /// `X-AppImage-Version` of the embedded desktop entry.
+    pub desktop_entry: Option<String>,
+    /// Newest release listed in the AppStream metadata.
+    pub appstream: Option<String>,
+    /// Product version of the `osu!.dll` assembly, without its build metadata.
+    pub assembly: Option<String>,
+}
+
+impl VersionInfo {
+    /// Returns the version the AppImage most likely is, preferring the sources that are written for packaging.
+    pub fn version(&self) -> Option<&str> {
+        self.desktop_entry
+            .as_deref()
+            .or(self.appstream.as_deref())
+            .or(self.assembly.as_deref())
+    }
+
+    /// Whether every source that was found reports the same version.
+    pub fn is_consistent(&self) -> bool {
+        let found = [&self.desktop_entry, &self.appstream, &self.assembly]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&String>>();
+
+        found.windows(2).all(|pair| pair[0] == pair[1])
+    }
+}
+
+/// Whether a version has the format of a release tag, e.g. `2023.617.0` or `2023.617.0-tachyon`.
+///
+/// Versions come from the contents of an AppImage, which can't be trusted to be usable as a file name.
+pub fn is_release_tag(version: &str) -> bool {
+    let number = version
+        .strip_suffix("-lazer")
+        .or_else(|| version.strip_suffix("-tachyon"))
+        .unwrap_or(version);
+
+    !number.is_empty()
+        && number
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// An icon bundled in an AppImage, along with the hicolor size directory it belongs to.
+#[derive(Debug)]
+pub struct Icon {
+    /// Either `<width>x<height>` or `scalable`.
+    pub size: String,
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+}
+
+impl Icon {
+    /// Detects the format and the size of an icon from its contents.
+    fn from_data(data: Vec<u8>) -> Option<Self> {
+        // The IHDR chunk always comes first, right after the signature.
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") && data.len() >= 24 {
+            let width = u32::from_be_bytes(data[16..20].try_into().unwrap());
+            let height = u32::from_be_bytes(data[20..24].try_into().unwrap());
+
+            return Some(Self {
+                size: format!("{}x{}", width, height),
+                extension: "png",
+                data,
+            });
+        }
+
+        let head = String::from_utf8_lossy(&data[..data.len().min(512)]).to_string();
+        if head.contains("<svg") {
+            return Some(Self {
+                size: "scalable".to_owned(),
+                extension: "svg",
+                data,
+            });
+        }
+
+        None
+    }
+}
+
+/// Read-only view of the squashfs payload embedded in a type 2 AppImage.
+///
+/// The payload is read in-process, so neither FUSE nor `unsquashfs` are needed.
+pub struct AppImage {
+    filesystem: FilesystemReader<'static>,
+}
+
+impl AppImage {
+    pub fn open(path: &Path) -> errors::Result<Self> {
+        let io_error = |e: io::Error| Error::Io {
+            source: e,
+            context: Some(path.to_string_lossy().to_string()),
+        };
+
+        let mut file = File::open(path).map_err(io_error)?;
+        let offset = get_payload_offset(&mut file).map_err(io_error)?;
+
+        let filesystem = FilesystemReader::from_reader_with_offset(BufReader::new(file), offset).map_err(|e| {
+            Error::Descriptive(format!(
+                "Couldn't read the AppImage payload of {}:\n{}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        Ok(Self { filesystem })
+    }
+
+    /// Lists the paths of every regular file in the payload, relative to its root.
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.filesystem
+            .files()
+            .filter(|node| matches!(node.inner, InnerNode::File(_)))
+            .map(|node| relative_path(&node.fullpath))
+            .collect()
+    }
+
+    /// Reads a file from the payload, following symlinks like `.DirIcon`.
+    ///
+    /// Returns [None] if there's no such file.
+    pub fn read_file(&self, path: &Path) -> errors::Result<Option<Vec<u8>>> {
+        let mut path = Path::new("/").join(path);
+
+        // Symlinks in AppImages are rarely nested, so a few hops are plenty.
+        for _ in 0..8 {
+            let node = match self.find_node(&path) {
+                Some(node) => node,
+                None => return Ok(None),
+            };
+
+            match &node.inner {
+                InnerNode::File(file) => return self.read_node(file).map(Some),
+                InnerNode::Symlink(symlink) => {
+                    let parent = path.parent().unwrap_or(Path::new("/"));
+                    path = normalize(&parent.join(&symlink.link));
+                }
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Unpacks the whole payload into dest, keeping permissions and symlinks.
+    pub fn extract_to(&self, dest: &Path) -> errors::Result<()> {
+        fs::create_dir_all(dest).map_err(|e| Error::Io {
+            source: e,
+            context: Some(dest.to_string_lossy().to_string()),
+        })?;
+
+        // Nodes are sorted by path, so a directory always comes before its contents.
+        for node in self.filesystem.files() {
+            let relative = relative_path(&node.fullpath);
+            if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+                return Err(Error::Descriptive(format!(
+                    "The AppImage contains an unsafe path: {}",
+                    node.fullpath.display()
+                )));
+            }
+
+            let entry = match &node.inner {
+                InnerNode::Dir(_) => Entry::Dir,
+                InnerNode::File(file) => Entry::File {
+                    data: self.read_node(file)?,
+                    mode: node.header.permissions,
+                },
+                InnerNode::Symlink(symlink) => Entry::Symlink(&symlink.link),
+                _ => continue,
+            };
+            extract_entry(dest, &relative, entry)?;
+        }
+
+        Ok(())
+    }
+
+    fn find_node(&self, path: &Path) -> Option<&Node<SquashfsFileReader>> {
+        self.filesystem.files().find(|node| node.fullpath == path)
+    }
+
+    /// Looks for the version of osu! the AppImage contains, without running or mounting it.
+    pub fn identify(&self) -> errors::Result<VersionInfo> {
+        let mut info = VersionInfo::default();
+
+        for path in self.file_paths() {
+            let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            let is_root = path.parent() == Some(Path::new(""));
+
+            if is_root && name.ends_with(".desktop") && info.desktop_entry.is_none() {
+                let content = self.read_file(&path)?.unwrap_or_default();
+                info.desktop_entry = parse_desktop_entry_version(&String::from_utf8_lossy(&content));
+            } else if (name.ends_with(".appdata.xml") || name.ends_with(".metainfo.xml")) && info.appstream.is_none() {
+                let content = self.read_file(&path)?.unwrap_or_default();
+                info.appstream = parse_appstream_version(&String::from_utf8_lossy(&content));
+            } else if name == "osu!.dll" && info.assembly.is_none() {
+                let content = self.read_file(&path)?.unwrap_or_default();
+                info.assembly = parse_assembly_version(&content);
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Reads the icons of osu! bundled in the hicolor theme of the payload, or `.DirIcon` if there are none.
+    pub fn icons(&self) -> errors::Result<Vec<Icon>> {
+        let mut icons = Vec::new();
+        for path in self.file_paths() {
+            if is_bundled_icon(&path) {
+                icons.extend(self.read_file(&path)?.and_then(Icon::from_data));
+            }
+        }
+
+        if icons.is_empty() {
+            icons.extend(self.read_file(Path::new(".DirIcon"))?.and_then(Icon::from_data));
+        }
+
+        Ok(icons)
+    }
+
+    fn read_node(&self, file: &SquashfsFileReader) -> errors::Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(file.file_len());
+        self.filesystem.file(file).reader().read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Finds where the squashfs payload starts, which is right after the ELF runtime.
+///
+/// The runtime ends with its section header table, so the offset is `e_shoff + e_shentsize * e_shnum`.
+fn get_payload_offset(reader: &mut (impl Read + Seek)) -> io::Result<u64> {
+    let mut header = [0; 64];
+    reader.seek(SeekFrom::Start(0))?;
+    reader.read_exact(&mut header)?;
+
+    if &header[..4] != b"\x7fELF" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an ELF file"));
+    }
+
+    let little_endian = header[5] == 1;
+    let u16_at = |at: usize| {
+        let bytes = [header[at], header[at + 1]];
+        if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let u32_at = |at: usize| {
+        let bytes = header[at..at + 4].try_into().unwrap();
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+    let u64_at = |at: usize| {
+        let bytes = header[at..at + 8].try_into().unwrap();
+        if little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        }
+    };
+
+    let (section_offset, entry_size, entry_count) = match header[4] {
+        1 => (u32_at(0x20) as u64, u16_at(0x2E), u16_at(0x30)),
+        2 => (u64_at(0x28), u16_at(0x3A), u16_at(0x3C)),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown ELF class")),
+    };
+
+    Ok(section_offset + entry_size as u64 * entry_count as u64)
+}
+
+/// Reads the icons of an AppImage extracted into dir, the same way as [AppImage::icons].
+pub fn extracted_icons(dir: &Path) -> io::Result<Vec<Icon>> {
+    let mut icons = Vec::new();
+
+    let hicolor_dir = dir.join("usr/share/icons/hicolor");
+    if hicolor_dir.is_dir() {
+        for size_dir in fs::read_dir(&hicolor_dir)? {
+            let apps_dir = size_dir?.path().join("apps");
+            if !apps_dir.is_dir() {
+                continue;
+            }
+
+            for entry in fs::read_dir(apps_dir)? {
+                let path = entry?.path();
+                let relative = path.strip_prefix(dir).unwrap_or(&path);
+                if is_bundled_icon(relative) {
+                    icons.extend(Icon::from_data(fs::read(&path)?));
+                }
+            }
+        }
+    }
+
+    let dir_icon = dir.join(".DirIcon");
+    if icons.is_empty() && dir_icon.exists() {
+        icons.extend(Icon::from_data(fs::read(dir_icon)?));
+    }
+
+    Ok(icons)
+}
+
+/// Whether the path is an osu! icon in the hicolor theme, like `usr/share/icons/hicolor/256x256/apps/osu.png`.
+fn is_bundled_icon(path: &Path) -> bool {
+    let components = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+
+    match components.as_slice() {
+        [.., theme, _size, apps, name] => {
+            theme == "hicolor" && apps == "apps" && name.to_lowercase().starts_with("osu")
+        }
+        _ => false,
+    }
+}
+
+fn parse_desktop_entry_version(content: &str) -> Option<String> {
+    let mut in_main_group = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+        } else if in_main_group {
+            if let Some(value) = line.strip_prefix("X-AppImage-Version=") {
+                return non_empty(value);
+            }
+        }
+    }
+
+    None
+}
+
+/// Releases are listed newest first, so the first `version` attribute of a `<release>` is the current one.
+fn parse_appstream_version(content: &str) -> Option<String> {
+    let release = &content[content.find("<release ")?..];
+    let release = &release[..release.find('>')?];
+
+    let value = &release[release.find("version=")? + "version=".len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+
+    non_empty(&value[..value.find(quote)?])
+}
+
+/// Reads `ProductVersion`, or `FileVersion` as a fallback, from the version resource of a PE assembly.
+///
+/// Both are stored as UTF-16 key and value pairs, with the value following the key after some padding.
+fn parse_assembly_version(content: &[u8]) -> Option<String> {
+    let find_value = |key: &str| -> Option<String> {
+        let needle = utf16_bytes(&format!("{}\0", key));
+        let start = content.windows(needle.len()).position(|window| window == needle)? + needle.len();
+
+        let value = content[start..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .skip_while(|c| *c == 0)
+            .take_while(|c| *c != 0)
+            .collect::<Vec<u16>>();
+
+        // .NET appends the commit after a '+' to the informational version.
+        let value = String::from_utf16(&value).ok()?;
+        non_empty(value.split('+').next().unwrap_or_default())
+    };
+
+    find_value("ProductVersion").or_else(|| find_value("FileVersion"))
+}
+
+fn utf16_bytes(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+/// A node of the payload, as it's written to disk.
+enum Entry<'a> {
+    Dir,
+    File { data: Vec<u8>, mode: u16 },
+    Symlink(&'a Path),
+}
+
+/// Writes an entry at `relative` within dest, which must be a safe relative path.
+///
+/// Nothing is written through a symlink or over an existing file, so a symlink in the payload can't redirect the
+/// entries after it outside of dest.
+fn extract_entry(dest: &Path, relative: &Path, entry: Entry) -> errors::Result<()> {
+    let io_error = |path: &Path| {
+        let context = path.to_string_lossy().to_string();
+        move |e: io::Error| Error::Io {
+            source: e,
+            context: Some(context),
+        }
+    };
+
+    let mut ancestor = dest.to_path_buf();
+    for component in relative.parent().unwrap_or(Path::new("")).components() {
+        ancestor.push(component);
+        if fs::symlink_metadata(&ancestor)
+            .map_err(io_error(&ancestor))?
+            .file_type()
+            .is_symlink()
+        {
+            return Err(Error::Descriptive(format!(
+                "The AppImage contains a path that goes through a symlink: {}",
+                relative.display()
+            )));
+        }
+    }
+
+    let target = dest.join(relative);
+    match entry {
+        Entry::Dir => fs::create_dir_all(&target).map_err(io_error(&target))?,
+        Entry::File { data, mode } => {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&target)
+                .map_err(io_error(&target))?;
+            file.write_all(&data).map_err(io_error(&target))?;
+            set_mode(&file, mode).map_err(io_error(&target))?;
+        }
+        Entry::Symlink(link) => {
+            #[cfg(target_family = "unix")]
+            std::os::unix::fs::symlink(link, &target).map_err(io_error(&target))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn relative_path(fullpath: &Path) -> PathBuf {
+    fullpath.strip_prefix("/").unwrap_or(fullpath).to_path_buf()
+}
+
+/// Resolves `.` and `..` components lexically, without touching the local filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(part) => normalized.push(part),
+            _ => {}
+        }
+    }
+
+    normalized
+}
+
+#[cfg(target_family = "unix")]
+fn set_mode(file: &File, mode: u16) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    file.set_permissions(fs::Permissions::from_mode(mode as u32 & 0o777))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn set_mode(_file: &File, _mode: u16) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    #[test]
+    fn payload_offset_follows_section_headers() {
+        let mut header = vec![0; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = 2;
+        header[5] = 1;
+        header[0x28..0x30].copy_from_slice(&0x2_8000u64.to_le_bytes());
+        header[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        header[0x3C..0x3E].copy_from_slice(&30u16.to_le_bytes());
+
+        let offset = super::get_payload_offset(&mut Cursor::new(header)).unwrap();
+        assert_eq!(offset, 0x2_8000 + 64 * 30);
+    }
+
+    #[test]
+    fn versions_are_parsed_from_metadata() {
+        let desktop_entry = "[Desktop Entry]\nName=osu!\nX-AppImage-Version=2023.617.0\n";
+        assert_eq!(
+            super::parse_desktop_entry_version(desktop_entry).as_deref(),
+            Some("2023.617.0")
+        );
+
+        let appstream =
+            "<releases>\n  <release version=\"2023.617.0\" date=\"2023-06-17\"/>\n  <release version=\"2023.612.0\"/>";
+        assert_eq!(super::parse_appstream_version(appstream).as_deref(), Some("2023.617.0"));
+
+        let mut assembly = b"MZ\0\0".to_vec();
+        assembly.extend(super::utf16_bytes("ProductVersion\0\0"));
+        assembly.extend(super::utf16_bytes("2023.617.0+0123abc\0"));
+        assert_eq!(super::parse_assembly_version(&assembly).as_deref(), Some("2023.617.0"));
+    }
+
+    #[test]
+    fn only_release_tags_are_accepted() {
+        for version in ["2023.617.0", "2023.617.0-lazer", "2023.617.0-tachyon"] {
+            assert!(super::is_release_tag(version), "{}", version);
+        }
+
+        for version in [
+            "",
+            "../../.bashrc",
+            "2023.617.0/../x",
+            "2023..0",
+            "2023.617.0-other",
+            "-tachyon",
+        ] {
+            assert!(!super::is_release_tag(version), "{}", version);
+        }
+    }
+
+    #[test]
+    fn icon_size_is_read_from_the_png_header() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(256u32.to_be_bytes());
+        png.extend(256u32.to_be_bytes());
+
+        let icon = super::Icon::from_data(png).unwrap();
+        assert_eq!(icon.size, "256x256");
+        assert_eq!(icon.extension, "png");
+    }
+
+    #[test]
+    fn normalize_resolves_relative_symlinks() {
+        let path = std::path::Path::new("/usr/bin/../share/icons/./osu.png");
+        assert_eq!(super::normalize(path), std::path::Path::new("/usr/share/icons/osu.png"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn extraction_never_follows_symlinks() {
+        use super::{extract_entry, Entry};
+        use std::fs;
+        use std::path::Path;
+
+        let root = std::env::temp_dir().join(format!("osu-helper-extract-{}", std::process::id()));
+        let dest = root.join("dest");
+        let outside = root.join("outside");
+        fs::create_dir_all(&dest).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("victim"), "original").unwrap();
+
+        // A symlinked directory, followed by a file within it.
+        extract_entry(&dest, Path::new("lib"), Entry::Symlink(&outside)).unwrap();
+        let file = Entry::File {
+            data: b"payload".to_vec(),
+            mode: 0o4755,
+        };
+        assert!(extract_entry(&dest, Path::new("lib/victim"), file).is_err());
+
+        // A file written over an existing symlink.
+        extract_entry(&dest, Path::new("AppRun"), Entry::Symlink(&outside.join("victim"))).unwrap();
+        let file = Entry::File {
+            data: b"payload".to_vec(),
+            mode: 0o755,
+        };
+        assert!(extract_entry(&dest, Path::new("AppRun"), file).is_err());
+
+        assert_eq!(fs::read_to_string(outside.join("victim")).unwrap(), "original");
+
+        // Special permission bits are dropped.
+        let file = Entry::File {
+            data: b"payload".to_vec(),
+            mode: 0o4755,
+        };
+        extract_entry(&dest, Path::new("osu!"), file).unwrap();
+        let mode = std::os::unix::fs::PermissionsExt::mode(&fs::metadata(dest.join("osu!")).unwrap().permissions());
+        assert_eq!(mode & 0o7777, 0o755);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Files and directories that are only valid once an operation completes, and must be removed if it doesn't.
+static PENDING_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Files that are being changed by an operation, along with their original state to restore if it doesn't complete.
+static PENDING_RESTORES: Mutex<Vec<(PathBuf, Original)>> = Mutex::new(Vec::new());
+
+/// Installs a SIGINT handler that removes every pending file before exiting.
+pub fn install_interrupt_handler() {
+    let result = ctrlc::set_handler(|| {
+        let pending_files = PENDING_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        for path in pending_files.iter() {
+            remove(path);
+        }
+
+        let pending_restores = PENDING_RESTORES.lock().unwrap_or_else(|e| e.into_inner());
+        for (path, original) in pending_restores.iter().rev() {
+            restore(path, original);
+        }
+
+        if pending_files.is_empty() && pending_restores.is_empty() {
+            eprintln!("\nInterrupted.");
+        } else {
+            eprintln!(
+                "\nInterrupted, removed {} partially written file(s) and restored {} changed file(s).",
+                pending_files.len(),
+                pending_restores.len()
+            );
+        }
+
+        std::process::exit(130);
+    });
+
+    if let Err(e) = result {
+        eprintln!("Couldn't install the interrupt handler: {}", e);
+    }
+}
+
+/// Guard for a file or directory that is removed on drop, unless the operation writing it is committed.
+///
+/// The file is also removed if the process is interrupted while the guard is alive.
+pub struct PendingFile {
+    path: PathBuf,
+    committed: bool,
+}
+
+impl PendingFile {
+    pub fn new(path: &Path) -> Self {
+        let mut pending_files = PENDING_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        pending_files.push(path.to_path_buf());
+
+        Self {
+            path: path.to_path_buf(),
+            committed: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keeps the file in place.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        let mut pending_files = PENDING_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        pending_files.retain(|path| path != &self.path);
+
+        if !self.committed {
+            remove(&self.path);
+        }
+    }
+}
+
+/// The state of a file before it was changed.
+enum Original {
+    Missing,
+    File(Vec<u8>, fs::Permissions),
+    Symlink(PathBuf),
+}
+
+/// Guard for a file that is put back in its original state on drop, unless the operation changing it is committed.
+///
+/// The original state is kept in memory, so this is only meant for small files and links, e.g. desktop entries.
+/// It's also restored if the process is interrupted while the guard is alive.
+pub struct PendingRestore {
+    path: PathBuf,
+    committed: bool,
+}
+
+impl PendingRestore {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let original = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => Original::Symlink(fs::read_link(path)?),
+            Ok(metadata) => Original::File(fs::read(path)?, metadata.permissions()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Original::Missing,
+            Err(e) => return Err(e),
+        };
+
+        let mut pending_restores = PENDING_RESTORES.lock().unwrap_or_else(|e| e.into_inner());
+        pending_restores.push((path.to_path_buf(), original));
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            committed: false,
+        })
+    }
+
+    /// Keeps the changes to the file.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for PendingRestore {
+    fn drop(&mut self) {
+        let mut pending_restores = PENDING_RESTORES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = pending_restores.iter().rposition(|(path, _)| path == &self.path) {
+            let (path, original) = pending_restores.remove(index);
+            if !self.committed {
+                restore(&path, &original);
+            }
+        }
+    }
+}
+
+/// Puts a file back in its original state, ignoring any errors as there is nothing left to do about them.
+fn restore(path: &Path, original: &Original) {
+    let _ = fs::remove_file(path);
+    match original {
+        Original::Missing => {}
+        Original::File(content, permissions) => {
+            let _ = fs::write(path, content).and_then(|_| fs::set_permissions(path, permissions.clone()));
+        }
+        #[cfg(target_family = "unix")]
+        Original::Symlink(target) => {
+            let _ = std::os::unix::fs::symlink(target, path);
+        }
+        #[cfg(not(target_family = "unix"))]
+        Original::Symlink(_) => {}
+    }
+}
+
+/// Removes a pending file or directory, ignoring any errors as there is nothing left to do about them.
+fn remove(path: &Path) {
+    if path.is_dir() {
+        let _ = fs::remove_dir_all(path);
+    } else {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uncommitted_changes_are_restored() {
+        let dir = std::env::temp_dir().join(format!("osu-helper-restore-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("entry.desktop");
+        let missing_path = dir.join("missing.desktop");
+        fs::write(&file_path, "original").unwrap();
+
+        {
+            let _file = PendingRestore::new(&file_path).unwrap();
+            let _missing = PendingRestore::new(&missing_path).unwrap();
+            fs::write(&file_path, "changed").unwrap();
+            fs::write(&missing_path, "created").unwrap();
+        }
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "original");
+        assert!(!missing_path.exists());
+
+        let file = PendingRestore::new(&file_path).unwrap();
+        fs::write(&file_path, "changed").unwrap();
+        file.commit();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "changed");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,3 +1,6 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -9,18 +12,172 @@ pub struct Cli {
     #[arg(long)]
     /// "The base directory to install different versions of osu!"
     pub install_dir: Option<String>,
+    /// Fail immediately instead of waiting when another instance is modifying the install directory
+    #[arg(long, global = true)]
+    pub no_wait: bool,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Install a specified version of osu!
-    Install { osu_version: Option<String> },
+    Install {
+        osu_version: Option<String>,
+        /// Install a local AppImage instead, detecting its version from its contents
+        #[arg(long, value_name = "FILE", conflicts_with = "osu_version")]
+        from_file: Option<PathBuf>,
+        /// Unpack the AppImage instead of keeping it as is, for systems without FUSE
+        ///
+        /// Overrides the install mode set in the config.
+        #[arg(long)]
+        extract: bool,
+    },
     /// Uninstall a specified version of osu!
     Remove { osu_version: Option<String> },
+    /// Show or change the settings in the config file
+    ///
+    /// Settings can also be overridden with environment variables, like OSU_HELPER_INSTALL_DIR for install.dir.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Diagnose problems with the osu! installation and desktop integration
+    ///
+    /// Exits with a non-zero status if any errors are found.
+    Doctor,
+    /// Detect the version of an osu! AppImage from its contents
+    Identify { file: PathBuf },
+    /// Show details about a specific version of osu!
+    Info { osu_version: String },
+    /// Give a version of osu! its own data directory, so it can't touch the main osu! data
+    ///
+    /// The data directory starts out empty, unless --seed is given.
+    Isolate {
+        osu_version: String,
+        /// Fill the data directory with a copy of the main osu! data, replacing what's in it
+        #[arg(long)]
+        seed: bool,
+    },
+    /// Let an isolated version of osu! use the main osu! data again
+    Unisolate {
+        osu_version: String,
+        /// Delete the data directory of the version as well
+        #[arg(long)]
+        delete_data: bool,
+    },
+    /// Start an installed version of osu!
+    ///
+    /// Arguments after -- are passed to the game, like a beatmap to import.
+    Launch {
+        /// Version to start, the newest installed one by default
+        osu_version: Option<String>,
+        /// Start the version marked as active instead of the newest one
+        #[arg(long, conflicts_with = "osu_version")]
+        active: bool,
+        /// Launch profile to use, overriding the default one from the config
+        #[arg(long)]
+        profile: Option<String>,
+        /// Look for a newer release before starting, even if launch.check_updates is off in the config
+        #[arg(long, conflicts_with = "no_check_updates")]
+        check_updates: bool,
+        /// Start right away, even if launch.check_updates is on in the config
+        #[arg(long)]
+        no_check_updates: bool,
+        /// Run the game in a bubblewrap sandbox, which can only write to the osu! data directory
+        #[arg(long)]
+        sandbox: bool,
+        /// Print the command that would start the game instead of starting it
+        #[arg(long)]
+        print_command: bool,
+        /// Start the game in the background and return right away
+        #[arg(long)]
+        detach: bool,
+        /// Write the output of the game to a log file in the logs directory of the install directory
+        #[arg(long)]
+        log: bool,
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// List installed versions of osu!
+    List {
+        /// List the releases available on GitHub instead
+        #[arg(long)]
+        remote: bool,
+    },
+    /// Keep a version of osu! from being removed by updates or prune
+    Pin { osu_version: String },
+    /// Allow a pinned version of osu! to be removed by updates or prune again
+    Unpin { osu_version: String },
+    /// Remove versions of osu! that fall outside of the retention policy
+    ///
+    /// Keeps the newest versions of each channel, the active version and pinned versions.
+    Prune {
+        /// Number of newest versions to keep per channel, overriding the config
+        #[arg(long)]
+        keep: Option<NonZeroUsize>,
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation before removing
+        #[arg(long)]
+        no_confirm: bool,
+    },
+    /// Download a fresh copy of an installed version of osu!, keeping its desktop entry and settings
+    Reinstall { osu_version: String },
+    /// Recreate the desktop integration of installed versions without downloading them again
+    ///
+    /// Fixes permissions, restores the icon, rewrites desktop entries and removes entries of missing binaries.
+    Repair {
+        osu_version: Option<String>,
+        /// Repair every installed version
+        #[arg(long, conflicts_with = "osu_version")]
+        all: bool,
+    },
+    /// Go back to the version osu! was on before the last update
+    ///
+    /// Offers to restore the osu! database snapshot taken before updating.
+    Rollback,
+    /// Mark a version of osu! as the one launched by the "osu!" desktop entry
+    Use { osu_version: Option<String> },
     /// Update osu! to the latest version
+    ///
+    /// If a version is marked as active, it is moved to the new version.
     Update {
         /// Don't ask for confirmation before updating
         #[arg(long)]
         no_confirm: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective value of a setting
+    Get { key: String },
+    /// Change a setting in the config file
+    ///
+    /// Values are read as TOML, like 5, true or ["Game", "Music"]. Anything else is taken as a string.
+    Set { key: String, value: String },
+    /// Remove a setting from the config file, so it goes back to its default
+    Unset { key: String },
+    /// List every setting with its effective value and where it comes from
+    List,
+    /// Open the config file in $VISUAL or $EDITOR, and check it once the editor exits
+    Edit,
+    /// Print the path of the config file
+    Path,
+}
+
+impl Commands {
+    /// Whether the command modifies the install directory, and has to hold the install lock.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            Self::Config { .. }
+                | Self::Doctor
+                | Self::Identify { .. }
+                | Self::Info { .. }
+                | Self::Launch { .. }
+                | Self::List { .. }
+                | Self::Prune { dry_run: true, .. }
+        )
+    }
+}
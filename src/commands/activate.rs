@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use colored::*;
+use inquire::Select;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    local,
+};
+
+pub fn activate(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config, version: Option<String>) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    if installed_tags.is_empty() {
+        return Err(Error::Descriptive(
+            "You don't have any known versions installed.\nUse the install command to install a version.".to_owned(),
+        ));
+    }
+
+    let version_tag = match version {
+        Some(version) => {
+            if !installed_tags.contains(&version) {
+                let message = format!("Couldn't find an installed release with the tag {}", version);
+                return Err(Error::Descriptive(message));
+            }
+
+            version
+        }
+        None => Select::new("Choose a version to use!", installed_tags).prompt()?,
+    };
+
+    local::set_active_binary(&local_data_dir, &install_dir, config, &version_tag)?;
+    local::update_desktop_database(&local_data_dir)?;
+
+    println!("Now using {}!", version_tag.green());
+
+    Ok(())
+}
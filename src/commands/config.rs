@@ -0,0 +1,158 @@
+use std::process::Command;
+
+use colored::*;
+
+use crate::{
+    config::{self, Config, SETTINGS},
+    errors::{Error, Result},
+};
+
+pub fn config_get(key: String) -> Result<()> {
+    ensure_setting(&key)?;
+
+    let table = Config::load()?.to_table();
+    match config::get_value(&table, &key) {
+        Some(value) => println!("{}", format_value(value)),
+        None => return Err(Error::Descriptive(format!("{} isn't set.", key))),
+    }
+
+    Ok(())
+}
+
+pub fn config_set(key: String, value: String) -> Result<()> {
+    ensure_setting(&key)?;
+
+    let mut table = Config::load_file()?;
+    config::set_value(&mut table, &key, config::parse_value(&value));
+    save_checked(table)?;
+
+    println!("Set {} to {}", key, value.green());
+    warn_if_overridden(&key);
+
+    Ok(())
+}
+
+pub fn config_unset(key: String) -> Result<()> {
+    ensure_setting(&key)?;
+
+    let mut table = Config::load_file()?;
+    let (table_key, value_key) = key.split_once('.').expect("Settings should always be in a table");
+    if let Some(toml::Value::Table(parent)) = table.get_mut(table_key) {
+        parent.remove(value_key);
+        if parent.is_empty() {
+            table.remove(table_key);
+        }
+    }
+    save_checked(table)?;
+
+    println!("Removed {} from the config, it's back to its default.", key);
+    warn_if_overridden(&key);
+
+    Ok(())
+}
+
+pub fn config_list() -> Result<()> {
+    let file = Config::load_file()?;
+    let effective = Config::load()?.to_table();
+
+    for key in SETTINGS {
+        let value = match config::get_value(&effective, key) {
+            Some(value) => format_value(value),
+            None => {
+                println!("{:<32} {}", key, "(not set)".dimmed());
+                continue;
+            }
+        };
+
+        let env_var = config::get_env_var_name(key);
+        let source = if std::env::var_os(&env_var).is_some() {
+            format!("from {}", env_var).yellow()
+        } else if config::get_value(&file, key).is_some() {
+            "from the config file".green()
+        } else {
+            "default".dimmed()
+        };
+
+        println!("{:<32} {} ({})", key, value, source);
+    }
+
+    Ok(())
+}
+
+pub fn config_edit() -> Result<()> {
+    let path = Config::path();
+    if !path.try_exists()? {
+        Config::save_file(&toml::Table::new())?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+
+    // Through the shell, so editors with arguments like "code --wait" work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status()?;
+    if !status.success() {
+        return Err(Error::Descriptive(format!("The editor exited with {}.", status)));
+    }
+
+    let table = Config::load_file()?;
+    check(table).map_err(|e| {
+        Error::Descriptive(format!(
+            "{}\nYour changes are kept, use config edit again to fix them.",
+            e
+        ))
+    })?;
+
+    println!("The config at {} is valid.", path.display());
+
+    Ok(())
+}
+
+pub fn config_path() -> Result<()> {
+    println!("{}", Config::path().display());
+    Ok(())
+}
+
+fn ensure_setting(key: &str) -> Result<()> {
+    if !SETTINGS.contains(&key) {
+        return Err(Error::Descriptive(format!(
+            "There is no setting named {}.\nUse config list to see every setting, or config edit for tables like \
+            profiles.",
+            key
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that the table makes a valid config, without the environment variable overrides.
+fn check(table: toml::Table) -> Result<()> {
+    let config = Config::from_table(table).map_err(|e| Error::Descriptive(format!("Invalid config:\n{}", e)))?;
+    config.validate()
+}
+
+fn save_checked(table: toml::Table) -> Result<()> {
+    check(table.clone())?;
+    Config::save_file(&table)
+}
+
+fn warn_if_overridden(key: &str) {
+    let env_var = config::get_env_var_name(key);
+    if std::env::var_os(&env_var).is_some() {
+        let message = format!("{} is set, which takes precedence over the config file.", env_var);
+        println!("{}", message.yellow());
+    }
+}
+
+/// Formats a value for printing, without quotes around strings so it can be used in scripts.
+fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::*;
+
+use crate::{
+    config::{Config, InstallMode},
+    desktop_entry::DesktopEntry,
+    errors::{Error, Result},
+    local::{self, InstallData},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Info => write!(f, "{}", "[INFO]".blue()),
+            Self::Warning => write!(f, "{}", "[WARN]".yellow()),
+            Self::Error => write!(f, "{}", "[ERROR]".red()),
+        }
+    }
+}
+
+struct Finding {
+    severity: Severity,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Finding {
+    fn new(severity: Severity, message: impl Into<String>, fix: Option<&str>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            fix: fix.map(str::to_owned),
+        }
+    }
+}
+
+/// Directories that commonly hold shared libraries, used when `ldconfig` isn't available.
+const LIBRARY_DIRS: [&str; 6] = [
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/lib",
+    "/lib64",
+    "/lib/x86_64-linux-gnu",
+];
+
+pub fn doctor(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config) -> Result<()> {
+    let mut findings = Vec::new();
+
+    check_fuse(&local_data_dir, &install_dir, config, &mut findings)?;
+    check_tools(config, &mut findings);
+    check_permissions(&local_data_dir, &install_dir, &mut findings)?;
+    check_binaries(&local_data_dir, &install_dir, config, &mut findings)?;
+    check_desktop_entries(&local_data_dir, &install_dir, &mut findings)?;
+
+    if findings.is_empty() {
+        println!("{}", "Everything looks good!".green());
+        return Ok(());
+    }
+
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    for finding in &findings {
+        println!("{} {}", finding.severity, finding.message);
+        if let Some(fix) = &finding.fix {
+            println!("        {} {}", "fix:".bold(), fix);
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(Error::Descriptive(format!("\nFound {} error(s).", errors)));
+    }
+
+    Ok(())
+}
+
+fn check_fuse(local_data_dir: &Path, install_dir: &Path, config: &Config, findings: &mut Vec<Finding>) -> Result<()> {
+    // Extracted installs run without FUSE, so it only matters if there are or will be AppImage installs.
+    let installed_tags = local::get_local_release_tags(install_dir)?;
+    let needs_fuse = if installed_tags.is_empty() {
+        config.install.mode == InstallMode::AppImage
+    } else {
+        installed_tags
+            .iter()
+            .any(|tag| !InstallData::new(local_data_dir, install_dir, tag).is_extracted())
+    };
+    if !needs_fuse {
+        return Ok(());
+    }
+
+    let in_ldconfig = std::process::Command::new("ldconfig")
+        .arg("-p")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("libfuse.so.2"))
+        .unwrap_or(false);
+
+    let in_library_dirs = LIBRARY_DIRS
+        .iter()
+        .any(|dir| Path::new(dir).join("libfuse.so.2").exists());
+
+    if !in_ldconfig && !in_library_dirs {
+        findings.push(Finding::new(
+            Severity::Error,
+            "libfuse2 wasn't found, AppImages won't be able to start.",
+            Some(
+                "Install libfuse2 (fuse2 on Arch, libfuse2 on Debian/Ubuntu, fuse-libs on Fedora), \
+                or set mode = \"extracted\" under [install] in the config.",
+            ),
+        ));
+    }
+
+    if !Path::new("/dev/fuse").exists() {
+        findings.push(Finding::new(
+            Severity::Error,
+            "/dev/fuse doesn't exist, AppImages can't be mounted.",
+            Some(
+                "Load the fuse kernel module (modprobe fuse), expose /dev/fuse to the container, \
+                or set mode = \"extracted\" under [install] in the config.",
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_tools(config: &Config, findings: &mut Vec<Finding>) {
+    if local::find_executable("update-desktop-database").is_none() {
+        findings.push(Finding::new(
+            Severity::Warning,
+            "update-desktop-database wasn't found, so desktop entries may not show up until you log in again.",
+            Some("Install desktop-file-utils."),
+        ));
+    }
+
+    if local::find_executable("update-mime-database").is_none() {
+        findings.push(Finding::new(
+            Severity::Warning,
+            "update-mime-database wasn't found, so .osz, .osk and .osr files may not open with osu!.",
+            Some("Install shared-mime-info."),
+        ));
+    }
+
+    let sandboxed_profiles = config.profiles.values().any(|profile| profile.sandbox);
+    if sandboxed_profiles && local::find_executable("bwrap").is_none() {
+        findings.push(Finding::new(
+            Severity::Error,
+            "bwrap wasn't found, so launch profiles with sandbox = true can't start the game.",
+            Some("Install bubblewrap."),
+        ));
+    }
+}
+
+fn check_permissions(local_data_dir: &Path, install_dir: &Path, findings: &mut Vec<Finding>) -> Result<()> {
+    let applications_dir = local_data_dir.join("applications");
+
+    for dir in [install_dir, applications_dir.as_path()] {
+        if !dir.try_exists()? {
+            findings.push(Finding::new(
+                Severity::Info,
+                format!("{} doesn't exist yet.", dir.display()),
+                None,
+            ));
+            continue;
+        }
+
+        if !is_writable(dir) {
+            findings.push(Finding::new(
+                Severity::Error,
+                format!("{} isn't writable.", dir.display()),
+                Some(&format!("Fix the ownership with: chown -R $USER \"{}\"", dir.display())),
+            ));
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    if install_dir.try_exists()? {
+        use std::os::unix::fs::PermissionsExt;
+
+        if fs::metadata(install_dir)?.permissions().mode() & 0o002 != 0 {
+            findings.push(Finding::new(
+                Severity::Error,
+                format!(
+                    "{} is world-writable, anyone on this system can replace the binaries.",
+                    install_dir.display()
+                ),
+                Some(&format!("chmod o-w \"{}\"", install_dir.display())),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_binaries(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(install_dir)?;
+    if installed_tags.is_empty() {
+        return Ok(());
+    }
+
+    if local::get_installed_icon_paths(local_data_dir)?.is_empty() {
+        findings.push(Finding::new(
+            Severity::Warning,
+            "The osu! icon isn't installed, desktop entries will show a generic icon.",
+            Some("Run the repair command to install the icon again."),
+        ));
+    }
+
+    for tag in &installed_tags {
+        let install_data = InstallData::new(local_data_dir, install_dir, tag);
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(install_data.exec_path())?.permissions().mode();
+            if mode & 0o111 == 0 {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    format!("The {} binary isn't executable.", tag),
+                    Some(&format!("Run: osu-helper-script repair {}", tag)),
+                ));
+            }
+        }
+
+        if config.desktop.writes_per_version_entries() && !install_data.desktop_entry_path.try_exists()? {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!("{} doesn't have a desktop entry.", tag),
+                Some(&format!("Run: osu-helper-script repair {}", tag)),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_desktop_entries(local_data_dir: &Path, install_dir: &Path, findings: &mut Vec<Finding>) -> Result<()> {
+    for entry_path in local::get_desktop_entry_paths(local_data_dir)? {
+        let problems = DesktopEntry::parse(&fs::read_to_string(&entry_path)?).validate();
+        if !problems.is_empty() {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!(
+                    "{} doesn't follow the desktop entry specification:\n        - {}",
+                    entry_path.display(),
+                    problems.join("\n        - ")
+                ),
+                Some("Run: osu-helper-script repair --all"),
+            ));
+        }
+
+        if let Some(reason) = local::get_orphan_reason(install_dir, &entry_path)? {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!("{} can't launch anything, as {}.", entry_path.display(), reason),
+                Some("Run: osu-helper-script repair --all"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+fn is_writable(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).expect("paths shouldn't contain null bytes");
+    unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(target_family = "unix"))]
+fn is_writable(path: &Path) -> bool {
+    fs::metadata(path).map_or(false, |metadata| !metadata.permissions().readonly())
+}
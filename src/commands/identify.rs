@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use colored::*;
+
+use crate::{
+    appimage::{self, AppImage},
+    errors::{Error, Result},
+};
+
+pub fn identify(file: PathBuf) -> Result<()> {
+    let version_info = AppImage::open(&file)?.identify()?;
+
+    let describe = |version: &Option<String>| match version {
+        Some(version) => version.normal(),
+        None => "not found".yellow(),
+    };
+    println!("Desktop entry:  {}", describe(&version_info.desktop_entry));
+    println!("AppStream:      {}", describe(&version_info.appstream));
+    println!("osu!.dll:       {}", describe(&version_info.assembly));
+
+    let version = match version_info.version() {
+        Some(version) => version,
+        None => {
+            return Err(Error::Descriptive(format!(
+                "Couldn't detect the version of {}.",
+                file.display()
+            )))
+        }
+    };
+
+    if !appimage::is_release_tag(version) {
+        return Err(Error::Descriptive(format!(
+            "{} reports an invalid version: {:?}",
+            file.display(),
+            version
+        )));
+    }
+
+    println!("Version:        {}", version.green());
+    if !version_info.is_consistent() {
+        println!(
+            "{}",
+            "The sources disagree on the version, the desktop entry is trusted first.".yellow()
+        );
+    }
+
+    Ok(())
+}
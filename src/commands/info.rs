@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use colored::*;
+use indicatif::HumanBytes;
+
+use crate::{
+    errors::{Error, Result},
+    github,
+    local::{self, InstallData},
+    manifest::Manifest,
+};
+
+use super::date_of;
+
+pub fn info(local_data_dir: PathBuf, install_dir: PathBuf, version: String) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    let is_installed = installed_tags.contains(&version);
+
+    let release = match github::get_release(&version) {
+        Ok(release) => Some(release),
+        Err(e) => match *e {
+            ureq::Error::Status(404, _) if !is_installed => {
+                return Err(Error::Descriptive(format!(
+                    "Couldn't find a release with the tag {}",
+                    version
+                )));
+            }
+            _ if is_installed => {
+                println!(
+                    "{}",
+                    "Couldn't fetch the release from GitHub, showing local data only.".yellow()
+                );
+                None
+            }
+            _ => return Err(Error::from(e)),
+        },
+    };
+
+    println!("{}", format!("osu! {}", version).bold());
+
+    if let Some(release) = &release {
+        println!("Channel:        {}", release.channel());
+        println!(
+            "Published:      {}",
+            release.published_at.as_deref().map_or("unknown", date_of)
+        );
+        println!("Release URL:    {}", release.html_url);
+        println!("Assets:");
+        for asset in &release.assets {
+            println!("  {:<32} {:>12}", asset.name, HumanBytes(asset.size).to_string());
+        }
+    }
+
+    if !is_installed {
+        println!("Installed:      {}", "no".yellow());
+        return Ok(());
+    }
+
+    let manifest = Manifest::load(&install_dir)?;
+    let entry = manifest.versions.get(&version);
+    let install_data = InstallData::new(&local_data_dir, &install_dir, &version);
+
+    if release.is_none() {
+        if let Some(entry) = entry {
+            println!("Channel:        {}", entry.channel);
+        }
+    }
+
+    println!(
+        "Installed:      {}",
+        entry.map_or("yes (date unknown)", |entry| date_of(&entry.installed_at))
+    );
+    println!("Install path:   {}", install_data.exec_path().display());
+    if manifest.isolated.contains(&version) {
+        let data_dir = local::get_isolated_data_home(&install_dir, &version).join("osu");
+        println!("osu! data:      {} (isolated)", data_dir.display());
+    }
+
+    let desktop_entry_state = if install_data.desktop_entry_path.try_exists()? {
+        "present".green()
+    } else {
+        "missing".yellow()
+    };
+    println!(
+        "Desktop entry:  {} ({})",
+        install_data.desktop_entry_path.display(),
+        desktop_entry_state
+    );
+
+    match entry.and_then(|entry| entry.sha256.as_ref()) {
+        // The recorded checksum is the one of the AppImage, which is gone once it's extracted.
+        Some(recorded) if install_data.is_extracted() => {
+            println!("SHA-256:        {} (of the AppImage, before extraction)", recorded)
+        }
+        Some(recorded) => {
+            let current = local::file_sha256(&install_data.install_path)?;
+            let state = if &current == recorded {
+                "matches the file on disk".green()
+            } else {
+                format!("DOES NOT match the file on disk ({})", current).red()
+            };
+
+            println!("SHA-256:        {} ({})", recorded, state);
+        }
+        None => println!("SHA-256:        {}", "not recorded".yellow()),
+    }
+
+    Ok(())
+}
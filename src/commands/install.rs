@@ -3,14 +3,26 @@ use std::path::PathBuf;
 use colored::*;
 use inquire::Select;
 
-use crate::{errors::Error, github, local};
+use crate::{config::Config, errors::Error, github, local};
+
+pub fn install(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    version: Option<String>,
+    from_file: Option<PathBuf>,
+) -> Result<(), Error> {
+    if let Some(from_file) = from_file {
+        let tag_name = local::install_from_file(&local_data_dir, &install_dir, config, &from_file)?;
+        println!("Successfully installed {}!", tag_name.green());
+        return Ok(());
+    }
 
-pub fn install(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<String>) -> Result<(), Error> {
     let installed_versions = local::get_local_release_tags(&install_dir)?;
     let release = match version {
         Some(version) => {
             let release = if version.to_lowercase() == "latest" {
-                github::get_latest_release()
+                github::get_latest_release(config.update.channel, None)
             } else {
                 github::get_release(&version)
             };
@@ -45,7 +57,7 @@ pub fn install(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<St
         }
     };
 
-    local::initialize_binary(&local_data_dir, &install_dir, &release)?;
+    local::initialize_binary(&local_data_dir, &install_dir, config, &release)?;
 
     println!("Successfully installed {}!", release.tag_name.green());
 
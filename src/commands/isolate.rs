@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use colored::*;
+use indicatif::HumanBytes;
+use inquire::Confirm;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    local,
+    manifest::Manifest,
+};
+
+pub fn isolate(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    version: String,
+    seed: bool,
+) -> Result<()> {
+    ensure_installed(&install_dir, &version)?;
+
+    let mut manifest = Manifest::load(&install_dir)?;
+    if manifest.isolated.insert(version.clone()) {
+        manifest.save(&install_dir)?;
+        refresh_desktop_entries(&local_data_dir, &install_dir, config, &version)?;
+    }
+
+    if seed {
+        let existing_size = local::get_isolated_data_size(&install_dir, &version)?;
+        let message = format!(
+            "Replace the {} of isolated data {} already has?",
+            HumanBytes(existing_size),
+            version
+        );
+        if existing_size > 0 && !Confirm::new(&message).with_default(false).prompt()? {
+            return Err(Error::Abort);
+        }
+
+        local::seed_isolated_data(&install_dir, &version)?;
+    }
+
+    println!(
+        "{} keeps its osu! data in {}",
+        version.green(),
+        local::get_isolated_data_home(&install_dir, &version)
+            .join("osu")
+            .display()
+    );
+
+    Ok(())
+}
+
+pub fn unisolate(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    version: String,
+    delete_data: bool,
+) -> Result<()> {
+    ensure_installed(&install_dir, &version)?;
+
+    let mut manifest = Manifest::load(&install_dir)?;
+    if manifest.isolated.remove(&version) {
+        manifest.save(&install_dir)?;
+        refresh_desktop_entries(&local_data_dir, &install_dir, config, &version)?;
+    }
+
+    let data_home = local::get_isolated_data_home(&install_dir, &version);
+    if delete_data {
+        let message = format!(
+            "Delete the {} of isolated data of {}?",
+            HumanBytes(local::get_isolated_data_size(&install_dir, &version)?),
+            version
+        );
+        if !Confirm::new(&message).with_default(true).prompt()? {
+            return Err(Error::Abort);
+        }
+
+        local::remove_isolated_data(&install_dir, &version)?;
+        println!("Deleted the isolated data of {}.", version.green());
+    } else if data_home.try_exists()? {
+        println!(
+            "The isolated data of {} is kept in {}, use --delete-data to delete it.",
+            version.green(),
+            data_home.display()
+        );
+    }
+
+    println!("{} uses the main osu! data again.", version.green());
+
+    Ok(())
+}
+
+fn ensure_installed(install_dir: &Path, version: &str) -> Result<()> {
+    if !local::get_local_release_tags(install_dir)?
+        .iter()
+        .any(|tag| tag == version)
+    {
+        let message = format!("Couldn't find an installed release with the tag {}", version);
+        return Err(Error::Descriptive(message));
+    }
+
+    Ok(())
+}
+
+/// Rewrites the entries that launch the version, which have to go through the launch command while it's isolated.
+fn refresh_desktop_entries(local_data_dir: &Path, install_dir: &Path, config: &Config, version: &str) -> Result<()> {
+    local::integrate_binary(local_data_dir, install_dir, config, version)?;
+    if let Some(active_tag) = local::get_active_tag(install_dir)? {
+        local::set_active_binary(local_data_dir, install_dir, config, &active_tag)?;
+    }
+
+    local::update_desktop_database(local_data_dir)
+}
@@ -0,0 +1,445 @@
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+use colored::*;
+use inquire::Confirm;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands,
+    config::{Config, UpdateAction},
+    errors::{Error, Result},
+    github::{self, Channel},
+    local::{self, InstallData},
+    lock::InstallLock,
+    manifest::Manifest,
+    sandbox,
+};
+
+/// Caches the result of the last update check in the install_dir.
+const UPDATE_CHECK_FILE_NAME: &str = "update-check.json";
+
+pub struct LaunchOptions {
+    /// Start the active version instead of the newest one.
+    pub active: bool,
+    pub profile: Option<String>,
+    /// Look for a newer release first, unless a version was given.
+    pub check_updates: bool,
+    /// Run the game under bwrap, which a profile can also ask for.
+    pub sandbox: bool,
+    /// Only print the command that would start the game.
+    pub print_command: bool,
+    pub detach: bool,
+    pub log: bool,
+}
+
+pub fn launch(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    version: Option<String>,
+    args: Vec<String>,
+    options: LaunchOptions,
+) -> Result<()> {
+    let LaunchOptions {
+        active,
+        profile,
+        check_updates,
+        sandbox,
+        print_command,
+        detach,
+        log,
+    } = options;
+    let profile = config.get_profile(profile.as_deref())?;
+
+    let mut installed_tags = local::get_local_release_tags(&install_dir)?;
+    // A version that was asked for by name is started as is.
+    if check_updates
+        && !print_command
+        && version.is_none()
+        && !installed_tags.is_empty()
+        && check_for_update(&local_data_dir, &install_dir, config, &installed_tags[0])
+    {
+        installed_tags = local::get_local_release_tags(&install_dir)?;
+    }
+
+    let active_tag = if active {
+        local::get_active_tag(&install_dir)?
+    } else {
+        None
+    };
+    if active && active_tag.is_none() {
+        return Err(Error::Descriptive(
+            "No version is marked as active.\nUse the use command to mark one.".to_owned(),
+        ));
+    }
+
+    let version_tag = match version.or(active_tag) {
+        Some(version) => {
+            if !installed_tags.contains(&version) {
+                let message = format!("Couldn't find an installed release with the tag {}", version);
+                return Err(Error::Descriptive(message));
+            }
+
+            version
+        }
+        None => match installed_tags.into_iter().next() {
+            Some(newest) => newest,
+            None => {
+                return Err(Error::Descriptive(
+                    "You don't have any known versions installed.\nUse the install command to install a version."
+                        .to_owned(),
+                ))
+            }
+        },
+    };
+
+    let install_data = InstallData::new(&local_data_dir, &install_dir, &version_tag);
+    let sandbox = sandbox || profile.is_some_and(|(_, profile)| profile.sandbox);
+
+    let mut envs = profile
+        .map(|(_, profile)| profile.env.iter().map(|(key, value)| (key.into(), value.into())))
+        .into_iter()
+        .flatten()
+        .collect::<Vec<(OsString, OsString)>>();
+
+    // Set after the profile, so a profile can't point the game back at the main data by accident.
+    let isolated_data_home = Manifest::load(&install_dir)?
+        .isolated
+        .contains(&version_tag)
+        .then(|| local::get_isolated_data_home(&install_dir, &version_tag));
+    if let Some(data_home) = &isolated_data_home {
+        envs.push(("XDG_DATA_HOME".into(), data_home.clone().into_os_string()));
+    }
+
+    // Wrappers run the next one in line, and the innermost one runs the game.
+    let mut command_line = profile
+        .map(|(_, profile)| profile.wrappers.iter().flat_map(|wrapper| wrapper.split_whitespace()))
+        .into_iter()
+        .flatten()
+        .map(OsString::from)
+        .collect::<Vec<OsString>>();
+
+    let data_dirs = match &isolated_data_home {
+        Some(data_home) => vec![data_home.join("osu")],
+        None => get_main_data_dirs(),
+    };
+    if sandbox {
+        if local::find_executable("bwrap").is_none() {
+            return Err(Error::Descriptive(
+                "bwrap wasn't found, so the game can't be sandboxed.\nInstall bubblewrap.".to_owned(),
+            ));
+        }
+
+        let game_path = if install_data.is_extracted() {
+            install_data.extract_dir.canonicalize()?
+        } else {
+            install_data.install_path.canonicalize()?
+        };
+
+        command_line.push("bwrap".into());
+        command_line.extend(sandbox::bwrap_args(&config.sandbox, &game_path, &data_dirs)?);
+        command_line.push(install_data.canonical_exec_path()?.into_os_string());
+    } else {
+        command_line.push(install_data.exec_path().into_os_string());
+    }
+
+    if let Some((_, profile)) = profile {
+        command_line.extend(profile.args.iter().map(OsString::from));
+    }
+    command_line.extend(args.into_iter().map(OsString::from));
+
+    if print_command {
+        println!("{}", format_command_line(&envs, &command_line));
+        return Ok(());
+    }
+
+    if let Some((name, profile)) = profile {
+        println!("Using the {} launch profile.", name.cyan());
+        run_hooks("pre-launch", &profile.pre_launch)?;
+    }
+    if let Some(data_home) = &isolated_data_home {
+        println!("Using the isolated osu! data in {}", data_home.join("osu").display());
+    }
+    if sandbox {
+        println!("Running osu! in a bubblewrap sandbox.");
+        // bwrap can only bind directories that exist.
+        for dir in &data_dirs {
+            fs::create_dir_all(dir).map_err(|e| Error::Io {
+                source: e,
+                context: Some(dir.to_string_lossy().to_string()),
+            })?;
+        }
+    } else if let Some(data_home) = &isolated_data_home {
+        fs::create_dir_all(data_home).map_err(|e| Error::Io {
+            source: e,
+            context: Some(data_home.to_string_lossy().to_string()),
+        })?;
+    }
+
+    let mut command = Command::new(&command_line[0]);
+    command.args(&command_line[1..]).envs(envs);
+
+    if log {
+        let log_path = create_log_path(&install_dir, &version_tag)?;
+        let log_file = fs::File::create(&log_path).map_err(|e| Error::Io {
+            source: e,
+            context: Some(log_path.to_string_lossy().to_string()),
+        })?;
+
+        command.stdout(log_file.try_clone()?).stderr(log_file);
+        println!("Writing the output of osu! to {}", log_path.display());
+    } else if detach {
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+    }
+
+    if detach {
+        command.stdin(Stdio::null());
+        detach_from_terminal(&mut command);
+    }
+
+    println!("Launching osu! {}...", version_tag.green());
+    let mut child = command.spawn().map_err(|e| Error::Io {
+        source: e,
+        context: Some(command_line[0].to_string_lossy().to_string()),
+    })?;
+
+    let post_launch = profile
+        .map(|(_, profile)| profile.post_launch.as_slice())
+        .unwrap_or_default();
+    if detach {
+        println!("osu! is running in the background with the PID {}.", child.id());
+        if !post_launch.is_empty() {
+            println!(
+                "{}",
+                "Skipping the post-launch commands, as the game was detached.".yellow()
+            );
+        }
+        return Ok(());
+    }
+
+    let status = child.wait()?;
+    run_hooks("post-launch", post_launch)?;
+    if !status.success() {
+        return Err(Error::Descriptive(format!("osu! exited with {}.", status)));
+    }
+
+    Ok(())
+}
+
+/// Result of the last update check, reused until it's older than the configured interval.
+#[derive(Serialize, Deserialize)]
+struct UpdateCheck {
+    /// RFC 3339 timestamp of when GitHub was asked.
+    checked_at: String,
+    channel: Channel,
+    latest_tag: String,
+}
+
+/// Looks for a release newer than the newest installed version, and notifies about it or installs it as configured.
+///
+/// Nothing here may keep the game from starting, so failures are only reported. Returns whether an update was
+/// installed.
+fn check_for_update(local_data_dir: &Path, install_dir: &Path, config: &Config, newest_tag: &str) -> bool {
+    let latest_tag = match get_latest_tag(install_dir, config) {
+        Ok(latest_tag) => latest_tag,
+        Err(Error::Ureq(_)) => {
+            println!("{}", "Couldn't reach GitHub to check for updates.".yellow());
+            return false;
+        }
+        Err(e) => {
+            println!("{}", format!("Couldn't check for updates: {}", e).yellow());
+            return false;
+        }
+    };
+
+    if local::cmp_version_tag_ltr(newest_tag, &latest_tag) != Ordering::Less {
+        return false;
+    }
+
+    println!("An update is available! {} -> {}", newest_tag, latest_tag.green());
+    let interactive = std::io::stdin().is_terminal();
+    let install = match config.launch.on_update {
+        UpdateAction::Install => true,
+        UpdateAction::Prompt if interactive => Confirm::new("Install it before launching?")
+            .with_default(true)
+            .prompt()
+            .unwrap_or(false),
+        UpdateAction::Prompt | UpdateAction::Notify => false,
+    };
+
+    if !install {
+        // Launched from a desktop entry, nobody is watching the output.
+        if !interactive {
+            notify_update(newest_tag, &latest_tag);
+        }
+
+        return false;
+    }
+
+    // Like any other command that changes the install, an unfinished operation has to be dealt with first.
+    let result = InstallLock::acquire(install_dir, false).and_then(|_lock| {
+        commands::recover(local_data_dir, install_dir, config)?;
+        commands::update(local_data_dir.to_owned(), install_dir.to_owned(), config, true)
+    });
+    match result {
+        Ok(()) => true,
+        Err(Error::Ureq(_)) => {
+            println!("{}", "Couldn't reach GitHub to install the update.".yellow());
+            false
+        }
+        Err(e) => {
+            let message = format!(
+                "Couldn't install the update, starting the installed version instead:\n{}",
+                e
+            );
+            println!("{}", message.yellow());
+            false
+        }
+    }
+}
+
+/// Returns the tag of the latest release, from the cache if the last check is recent enough.
+fn get_latest_tag(install_dir: &Path, config: &Config) -> Result<String> {
+    let launch_config = &config.launch;
+    let channel = config.update.channel;
+    let path = install_dir.join(UPDATE_CHECK_FILE_NAME);
+    let interval = Duration::from_secs(launch_config.check_interval_minutes * 60);
+    let cached = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<UpdateCheck>(&content).ok())
+        .filter(|check| check.channel == channel)
+        .filter(|check| {
+            humantime::parse_rfc3339(&check.checked_at)
+                .ok()
+                .and_then(|checked_at| checked_at.elapsed().ok())
+                .is_some_and(|age| age < interval)
+        });
+    if let Some(check) = cached {
+        return Ok(check.latest_tag);
+    }
+
+    println!("Checking for updates...");
+    let timeout = Duration::from_secs(launch_config.check_timeout_seconds);
+    let release = github::get_latest_release(channel, Some(timeout))?;
+    let check = UpdateCheck {
+        channel,
+        checked_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        latest_tag: release.tag_name,
+    };
+
+    // The cache only saves a request, so not being able to write it isn't worth stopping over.
+    if let Ok(content) = serde_json::to_string(&check) {
+        let _ = fs::write(&path, content);
+    }
+
+    Ok(check.latest_tag)
+}
+
+/// Shows a desktop notification about the update, without waiting for it to be dismissed.
+fn notify_update(newest_tag: &str, latest_tag: &str) {
+    let body = format!(
+        "osu! {} is available, the newest installed version is {}.\nUse the update command to install it.",
+        latest_tag, newest_tag
+    );
+
+    // Not every system has a notification daemon, which shouldn't keep the game from starting.
+    let _ = Command::new("notify-send")
+        .args(["--app-name=osu!", "--icon=osu!", "osu! update available", &body])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+/// Returns the directories osu! may keep its data in: the default one, and the one `storage.ini` moved it to.
+fn get_main_data_dirs() -> Vec<PathBuf> {
+    let default_dir = dirs::data_dir()
+        .expect("Couldn't find your data directory.")
+        .join("osu");
+    let data_dir = local::get_osu_data_dir();
+
+    if data_dir == default_dir {
+        vec![default_dir]
+    } else {
+        vec![default_dir, data_dir]
+    }
+}
+
+/// Formats the environment and the command line so they can be pasted into a shell.
+fn format_command_line(envs: &[(OsString, OsString)], command_line: &[OsString]) -> String {
+    let envs = envs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key.to_string_lossy(), shell_quote(&value.to_string_lossy())));
+    let command_line = command_line.iter().map(|arg| shell_quote(&arg.to_string_lossy()));
+
+    envs.chain(command_line).collect::<Vec<String>>().join(" ")
+}
+
+/// Quotes an argument for a POSIX shell, unless it only has characters that don't need quoting.
+fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c));
+
+    if is_plain {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Runs each command through the shell, in order, stopping at the first one that fails.
+fn run_hooks(kind: &str, commands: &[String]) -> Result<()> {
+    for command in commands {
+        println!("Running the {} command: {}", kind, command);
+        let status = Command::new("sh").arg("-c").arg(command).status()?;
+        if !status.success() {
+            return Err(Error::Descriptive(format!(
+                "The {} command \"{}\" failed with {}.",
+                kind, command, status
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a new log file path in the logs directory, named after the version and the time of the launch.
+fn create_log_path(install_dir: &Path, version_tag: &str) -> Result<PathBuf> {
+    let logs_dir = install_dir.join("logs");
+    fs::create_dir_all(&logs_dir).map_err(|e| Error::Io {
+        source: e,
+        context: Some(logs_dir.to_string_lossy().to_string()),
+    })?;
+
+    let timestamp = humantime::format_rfc3339_seconds(SystemTime::now())
+        .to_string()
+        .replace(':', "-");
+    Ok(logs_dir.join(format!("{}-{}.log", version_tag, timestamp)))
+}
+
+/// Starts the game in its own session, so closing the terminal doesn't take it down with it.
+#[cfg(target_family = "unix")]
+fn detach_from_terminal(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: setsid is async-signal-safe, so it can be called between fork and exec.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+fn detach_from_terminal(_command: &mut Command) {}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::PathBuf;
+
+use colored::*;
+use indicatif::HumanBytes;
+
+use crate::{
+    config::Config,
+    errors::Result,
+    github,
+    local::{self, InstallData},
+    manifest::Manifest,
+};
+
+use super::date_of;
+
+pub fn list(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config, remote: bool) -> Result<()> {
+    if remote {
+        list_remote(install_dir)
+    } else {
+        list_local(local_data_dir, install_dir, config)
+    }
+}
+
+fn list_local(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    if installed_tags.is_empty() {
+        println!("You don't have any known versions installed.\nUse the install command to install a version.");
+        return Ok(());
+    }
+
+    let manifest = Manifest::load(&install_dir)?;
+    let active_tag = local::get_active_tag(&install_dir)?;
+    let has_active_entry = local_data_dir
+        .join("applications")
+        .join(local::ACTIVE_DESKTOP_ENTRY_NAME)
+        .try_exists()?;
+
+    println!(
+        "{:<14} {:>12}  {:<10}  {:<8}  DESKTOP ENTRY",
+        "VERSION", "SIZE", "INSTALLED", "CHANNEL"
+    );
+
+    for tag in installed_tags {
+        let install_data = InstallData::new(&local_data_dir, &install_dir, &tag);
+        let metadata = fs::metadata(install_data.exec_path())?;
+        let entry = manifest.versions.get(&tag);
+
+        // Versions installed before the manifest existed only have their modification time to go by.
+        let installed_at = match entry {
+            Some(entry) => entry.installed_at.clone(),
+            None => humantime::format_rfc3339_seconds(metadata.modified()?).to_string(),
+        };
+
+        let channel = entry.map_or_else(|| "unknown".to_owned(), |entry| entry.channel.to_string());
+        // Without an entry of its own, a version can still be launched from the "osu!" entry, or one of its actions.
+        let is_active = active_tag.as_ref() == Some(&tag);
+        let has_desktop_entry = if install_data.desktop_entry_path.try_exists()? {
+            "yes".green()
+        } else if has_active_entry && is_active {
+            "osu!".green()
+        } else if has_active_entry && config.desktop.actions {
+            "osu! action".green()
+        } else {
+            "no".yellow()
+        };
+
+        let mut markers = String::new();
+        if is_active {
+            markers.push_str(&" (active)".cyan().to_string());
+        }
+        if manifest.pinned.contains(&tag) {
+            markers.push_str(&" (pinned)".blue().to_string());
+        }
+
+        println!(
+            "{:<14} {:>12}  {:<10}  {:<8}  {}{}",
+            tag,
+            HumanBytes(local::get_install_size(&install_dir, &tag)?).to_string(),
+            date_of(&installed_at),
+            channel,
+            has_desktop_entry,
+            markers
+        );
+    }
+
+    Ok(())
+}
+
+fn list_remote(install_dir: PathBuf) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    let releases = github::get_releases()?;
+
+    let newest_tag = releases
+        .iter()
+        .map(|release| release.tag_name.as_str())
+        .max_by(|a, b| local::cmp_version_tag_ltr(a, b));
+
+    println!(
+        "{:<14} {:<10}  {:>12}  {:<8}",
+        "VERSION", "PUBLISHED", "SIZE", "CHANNEL"
+    );
+
+    for release in &releases {
+        let published_at = release.published_at.as_deref().map_or("unknown", date_of);
+        let size = match release.get_app_image_asset() {
+            Some(asset) => HumanBytes(asset.size).to_string(),
+            None => "no AppImage".to_owned(),
+        };
+
+        let mut markers = Vec::with_capacity(2);
+        if installed_tags.contains(&release.tag_name) {
+            markers.push("installed".green());
+        }
+        if newest_tag == Some(release.tag_name.as_str()) {
+            markers.push("newest".cyan());
+        }
+
+        let markers = markers.iter().map(|m| m.to_string()).collect::<Vec<String>>();
+        println!(
+            "{:<14} {:<10}  {:>12}  {:<8}  {}",
+            release.tag_name,
+            published_at,
+            size,
+            release.channel().to_string(),
+            markers.join(", ")
+        );
+    }
+
+    Ok(())
+}
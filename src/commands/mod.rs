@@ -1,7 +1,40 @@
+mod activate;
+mod config;
+mod doctor;
+mod identify;
+mod info;
 mod install;
+mod isolate;
+mod launch;
+mod list;
+mod pin;
+mod prune;
+mod recover;
+mod reinstall;
 mod remove;
+mod repair;
+mod rollback;
 mod update;
 
+pub use activate::activate;
+pub use config::{config_edit, config_get, config_list, config_path, config_set, config_unset};
+pub use doctor::doctor;
+pub use identify::identify;
+pub use info::info;
 pub use install::install;
+pub use isolate::{isolate, unisolate};
+pub use launch::{launch, LaunchOptions};
+pub use list::list;
+pub use pin::pin;
+pub use prune::prune;
+pub use recover::recover;
+pub use reinstall::reinstall;
 pub use remove::remove;
+pub use repair::repair;
+pub use rollback::rollback;
 pub use update::update;
+
+/// Extracts the date component of an RFC 3339 timestamp.
+fn date_of(timestamp: &str) -> &str {
+    timestamp.split('T').next().unwrap_or(timestamp)
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use colored::*;
+
+use crate::{
+    errors::{Error, Result},
+    local,
+    manifest::Manifest,
+};
+
+pub fn pin(install_dir: PathBuf, version: String, pinned: bool) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    if !installed_tags.contains(&version) {
+        let message = format!("Couldn't find an installed release with the tag {}", version);
+        return Err(Error::Descriptive(message));
+    }
+
+    let mut manifest = Manifest::load(&install_dir)?;
+    if pinned {
+        manifest.pinned.insert(version.clone());
+        println!("Pinned {}, it won't be removed by updates or prune.", version.green());
+    } else {
+        manifest.pinned.remove(&version);
+        println!("Unpinned {}.", version.green());
+    }
+
+    manifest.save(&install_dir)
+}
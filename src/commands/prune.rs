@@ -0,0 +1,60 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use colored::*;
+use indicatif::HumanBytes;
+use inquire::Confirm;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    local,
+};
+
+pub fn prune(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    keep: Option<NonZeroUsize>,
+    dry_run: bool,
+    no_confirm: bool,
+) -> Result<()> {
+    let keep = match keep.or(config.retention.keep) {
+        Some(keep) => keep,
+        None => {
+            return Err(Error::Descriptive(
+                "No retention policy is configured.\nPass --keep or set retention.keep in the config.".to_owned(),
+            ))
+        }
+    };
+
+    let prunable_tags = local::get_prunable_release_tags(&install_dir, keep)?;
+    if prunable_tags.is_empty() {
+        println!("Nothing to prune, all installed versions are within the retention policy.");
+        return Ok(());
+    }
+
+    let mut total_size = 0;
+    println!("The following versions would be removed:");
+    for tag in &prunable_tags {
+        let size = local::get_install_size(&install_dir, tag)?;
+        total_size += size;
+
+        println!("  {:<14} {:>12}", tag, HumanBytes(size).to_string());
+    }
+    println!("This frees up {}.", HumanBytes(total_size).to_string().green());
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if !no_confirm && !Confirm::new("Continue to remove?").with_default(true).prompt()? {
+        return Err(Error::Abort);
+    }
+
+    for tag in &prunable_tags {
+        local::remove_binary(&local_data_dir, &install_dir, config, tag)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use colored::*;
+use inquire::Select;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    journal::Journal,
+};
+
+const COMPLETE: &str = "Complete it";
+const ROLL_BACK: &str = "Roll it back";
+const LEAVE: &str = "Leave it for now";
+
+/// Checks for an operation that was interrupted in a previous run, and offers to complete or roll it back.
+///
+/// Without a terminal to ask on, the operation is completed. Leaving it aborts the command that was about to run.
+pub fn recover(local_data_dir: &Path, install_dir: &Path, config: &Config) -> Result<()> {
+    let mut journal = match Journal::load(install_dir)? {
+        Some(journal) => journal,
+        None => return Ok(()),
+    };
+
+    println!("{}", "A previous operation didn't finish:".yellow());
+    println!("{}", journal.describe());
+
+    let choice = if std::io::stdin().is_terminal() {
+        Select::new("What do you want to do?", vec![COMPLETE, ROLL_BACK, LEAVE]).prompt()?
+    } else {
+        COMPLETE
+    };
+
+    match choice {
+        COMPLETE => {
+            let operation = journal.operation.clone();
+            journal.run(local_data_dir, install_dir, config, None)?;
+            journal.finish(install_dir)?;
+            println!("Completed the unfinished {}.", operation);
+        }
+        ROLL_BACK => {
+            let operation = journal.operation.clone();
+            journal.roll_back(local_data_dir, install_dir, config)?;
+            println!("Rolled back the unfinished {}.", operation);
+        }
+        // Anything else would start a new operation, and overwrite the journal of this one.
+        _ => return Err(Error::Abort),
+    }
+
+    Ok(())
+}
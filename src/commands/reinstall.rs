@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use colored::*;
+
+use crate::{
+    errors::{Error, Result},
+    github, local,
+};
+
+pub fn reinstall(local_data_dir: PathBuf, install_dir: PathBuf, version: String) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    if !installed_tags.contains(&version) {
+        let message = format!(
+            "Couldn't find an installed release with the tag {}\nUse the install command to install it.",
+            version
+        );
+        return Err(Error::Descriptive(message));
+    }
+
+    let release = match github::get_release(&version) {
+        Ok(release) => release,
+        Err(e) => {
+            if let ureq::Error::Status(404, _) = *e {
+                return Err(Error::Descriptive(format!(
+                    "Couldn't find a release with the tag {}",
+                    version
+                )));
+            }
+
+            return Err(Error::from(e));
+        }
+    };
+
+    local::reinstall_binary(&local_data_dir, &install_dir, &release)?;
+
+    println!("Successfully reinstalled {}!", release.tag_name.green());
+
+    Ok(())
+}
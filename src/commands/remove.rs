@@ -1,13 +1,15 @@
+use std::fs;
 use std::path::PathBuf;
 
 use inquire::{Confirm, Select};
 
 use crate::{
+    config::Config,
     errors::{Error, Result},
     local,
 };
 
-pub fn remove(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<String>) -> Result<()> {
+pub fn remove(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config, version: Option<String>) -> Result<()> {
     let installed_tags = local::get_local_release_tags(&install_dir)?;
     if installed_tags.is_empty() {
         return Err(Error::Descriptive(
@@ -32,13 +34,49 @@ pub fn remove(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<Str
     };
 
     let confirm_etc_delete = || -> Result<()> {
+        // Isolated osu! data and database snapshots are worth more than the rest, so they're never removed here.
+        let kept_paths = [install_dir.join("data"), install_dir.join("snapshots")]
+            .into_iter()
+            .filter(|path| path.exists())
+            .collect::<Vec<PathBuf>>();
+
+        if kept_paths.is_empty() {
+            let message = format!(
+                "Do you want to remove the other files as well? THIS WILL DELETE {}",
+                install_dir.display()
+            );
+
+            if Confirm::new(&message).prompt()? {
+                fs::remove_dir_all(&install_dir)?;
+            }
+
+            return Ok(());
+        }
+
+        let kept = kept_paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<String>>();
         let message = format!(
-            "Do you want to remove the icon and other files as well? THIS WILL DELETE {}",
-            install_dir.display()
+            "Do you want to remove the other files as well? THIS WILL DELETE everything in {} but the osu! data in {}",
+            install_dir.display(),
+            kept.join(" and ")
         );
 
         if Confirm::new(&message).prompt()? {
-            std::fs::remove_dir_all(&install_dir)?;
+            for entry in fs::read_dir(&install_dir)? {
+                let path = entry?.path();
+                if kept_paths.contains(&path) {
+                    continue;
+                }
+
+                if path.is_dir() && !path.is_symlink() {
+                    fs::remove_dir_all(&path)?;
+                } else {
+                    fs::remove_file(&path)?;
+                }
+            }
+            println!("Kept the osu! data in {}.", kept.join(" and "));
         }
 
         Ok(())
@@ -48,7 +86,7 @@ pub fn remove(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<Str
         let message = format!("Are you sure you want to delete all {} versions?", installed_tags.len());
         if version_tag == "All" && Confirm::new(&message).prompt()? {
             for tag in installed_tags {
-                local::remove_binary(&local_data_dir, &install_dir, &tag)?;
+                local::remove_binary(&local_data_dir, &install_dir, config, &tag)?;
             }
 
             confirm_etc_delete()?;
@@ -59,7 +97,7 @@ pub fn remove(local_data_dir: PathBuf, install_dir: PathBuf, version: Option<Str
     {
         let message = format!("Are you sure you want to delete {}?", version_tag);
         if Confirm::new(&message).prompt()? {
-            local::remove_binary(&local_data_dir, &install_dir, &version_tag)?;
+            local::remove_binary(&local_data_dir, &install_dir, config, &version_tag)?;
 
             if installed_tags.len() == 1 {
                 confirm_etc_delete()?;
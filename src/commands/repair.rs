@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use colored::*;
+use inquire::Select;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    local,
+};
+
+pub fn repair(
+    local_data_dir: PathBuf,
+    install_dir: PathBuf,
+    config: &Config,
+    version: Option<String>,
+    all: bool,
+) -> Result<()> {
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    if installed_tags.is_empty() {
+        return Err(Error::Descriptive(
+            "You don't have any known versions installed.\nUse the install command to install a version.".to_owned(),
+        ));
+    }
+
+    let tags = match version {
+        _ if all => installed_tags,
+        Some(version) => {
+            if !installed_tags.contains(&version) {
+                let message = format!("Couldn't find an installed release with the tag {}", version);
+                return Err(Error::Descriptive(message));
+            }
+
+            vec![version]
+        }
+        None => {
+            let mut selection = installed_tags.clone();
+            selection.push("All".to_owned());
+
+            match Select::new("Choose a version to repair!", selection).prompt()? {
+                all if all == "All" => installed_tags,
+                version => vec![version],
+            }
+        }
+    };
+
+    for tag in &tags {
+        println!("Repairing {}...", tag.green());
+        local::integrate_binary(&local_data_dir, &install_dir, config, tag)?;
+    }
+
+    if let Some(active_tag) = local::get_active_tag(&install_dir)? {
+        if tags.contains(&active_tag) {
+            local::set_active_binary(&local_data_dir, &install_dir, config, &active_tag)?;
+        }
+    }
+
+    for (entry_path, reason) in local::remove_orphaned_desktop_entries(&local_data_dir, &install_dir)? {
+        println!("Removed {}, as {}.", entry_path.display(), reason);
+    }
+
+    local::update_desktop_database(&local_data_dir)?;
+
+    println!("Successfully repaired {} version(s)!", tags.len());
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use colored::*;
+use inquire::Confirm;
+
+use crate::{
+    config::Config,
+    errors::{Error, Result},
+    journal::{Action, Journal},
+    local,
+    manifest::Manifest,
+};
+
+pub fn rollback(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config) -> Result<()> {
+    let record = match Manifest::load(&install_dir)?.last_update {
+        Some(record) => record,
+        None => return Err(Error::Descriptive("There is no update to roll back.".to_owned())),
+    };
+
+    println!("Rolling back {} -> {}", record.to, record.from.green());
+
+    let installed_tags = local::get_local_release_tags(&install_dir)?;
+    let mut actions = Vec::new();
+
+    if !installed_tags.contains(&record.from) {
+        println!(
+            "{} was removed by the update, it will be downloaded again.",
+            record.from
+        );
+        actions.push(Action::Install {
+            tag: record.from.clone(),
+        });
+    }
+
+    let active_tag = local::get_active_tag(&install_dir)?;
+    if active_tag.is_some() {
+        actions.push(Action::SetActive {
+            tag: record.from.clone(),
+            previous: active_tag,
+        });
+    }
+
+    let message = format!("Do you want to remove {}?", record.to);
+    if installed_tags.contains(&record.to) && Confirm::new(&message).with_default(true).prompt()? {
+        actions.push(Action::Remove { tag: record.to.clone() });
+    }
+
+    let restore_snapshot = match &record.realm_snapshot {
+        Some(snapshot_path) if snapshot_path.try_exists()? => {
+            let message = format!(
+                "Restore the osu! database snapshot taken before updating to {}? Changes made since will be lost.",
+                record.to
+            );
+
+            Confirm::new(&message).with_default(true).prompt()?
+        }
+        _ => false,
+    };
+
+    actions.push(Action::UpdateDesktopDatabase);
+
+    let mut journal = Journal::begin(&install_dir, "rollback", actions)?;
+    journal.run(&local_data_dir, &install_dir, config, None)?;
+    journal.finish(&install_dir)?;
+
+    if let (true, Some(snapshot_path)) = (restore_snapshot, &record.realm_snapshot) {
+        local::restore_realm(snapshot_path)?;
+    }
+
+    let mut manifest = Manifest::load(&install_dir)?;
+    manifest.last_update = None;
+    manifest.save(&install_dir)?;
+
+    println!("Successfully rolled back to {}!", record.from.green());
+
+    Ok(())
+}
@@ -1,9 +1,16 @@
 use inquire::Confirm;
 use std::{cmp::Ordering, path::PathBuf};
 
-use crate::{errors::Error, github, local};
+use crate::{
+    config::Config,
+    errors::Error,
+    github,
+    journal::{Action, Journal},
+    local,
+    manifest::{Manifest, UpdateRecord},
+};
 
-pub fn update(local_data_dir: PathBuf, install_dir: PathBuf, no_confirm: bool) -> Result<(), Error> {
+pub fn update(local_data_dir: PathBuf, install_dir: PathBuf, config: &Config, no_confirm: bool) -> Result<(), Error> {
     let installed_tags = local::get_local_release_tags(&install_dir)?;
     if installed_tags.is_empty() {
         return Err(Error::Descriptive(
@@ -12,7 +19,7 @@ pub fn update(local_data_dir: PathBuf, install_dir: PathBuf, no_confirm: bool) -
     }
 
     let latest_local_tag = &installed_tags[0];
-    let latest_release = github::get_latest_release()?;
+    let latest_release = github::get_latest_release(config.update.channel, None)?;
 
     match local::cmp_version_tag_ltr(latest_local_tag, &latest_release.tag_name) {
         Ordering::Less => {
@@ -40,8 +47,50 @@ pub fn update(local_data_dir: PathBuf, install_dir: PathBuf, no_confirm: bool) -
         return Err(Error::Abort);
     }
 
-    local::initialize_binary(&local_data_dir, &install_dir, &latest_release)?;
-    local::remove_binary(&local_data_dir, &install_dir, latest_local_tag)?;
+    // The snapshot and the download both land in the install_dir, so check for room for both up front.
+    let download_size = latest_release.get_app_image_asset().map_or(0, |asset| asset.size);
+    local::ensure_free_space(&install_dir, &[&install_dir], download_size + local::get_realm_size()?)?;
+
+    let realm_snapshot = local::snapshot_realm(&install_dir, latest_local_tag)?;
+    let new_tag = latest_release.tag_name.clone();
+
+    // The whole update is planned up front, so an interrupted one can be completed from its journal alone.
+    let removed_tags = match config.retention.keep {
+        Some(keep) => {
+            local::get_prunable_release_tags_after_install(&install_dir, keep, &new_tag, latest_release.channel())?
+        }
+        None if !Manifest::load(&install_dir)?.pinned.contains(latest_local_tag) => vec![latest_local_tag.clone()],
+        None => Vec::with_capacity(0),
+    };
+
+    let mut actions = vec![Action::Install { tag: new_tag.clone() }];
+    let active_tag = local::get_active_tag(&install_dir)?;
+    if active_tag.is_some() && !config.desktop.follows_newest() {
+        actions.push(Action::SetActive {
+            tag: new_tag.clone(),
+            previous: active_tag,
+        });
+    }
+    actions.push(Action::RecordUpdate {
+        record: UpdateRecord {
+            from: latest_local_tag.clone(),
+            to: new_tag.clone(),
+            realm_snapshot,
+        },
+    });
+    // The version that was just installed is never removed, whatever the retention policy says.
+    actions.extend(
+        removed_tags
+            .into_iter()
+            .filter(|tag| *tag != new_tag)
+            .map(|tag| Action::Remove { tag }),
+    );
+    actions.push(Action::UpdateDesktopDatabase);
+
+    let mut journal = Journal::begin(&install_dir, "update", actions)?;
+    journal.run(&local_data_dir, &install_dir, config, Some(&latest_release))?;
+    journal.finish(&install_dir)?;
+
     println!("Successfully updated to {}!", &latest_release.tag_name);
 
     Ok(())
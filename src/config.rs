@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{self, Error};
+use crate::github::Channel;
+
+/// Settings the config command can read and change, and environment variables can override, as dotted keys.
+///
+/// Tables with keys of their own, like profiles or localized names, can only be changed by editing the file.
+pub const SETTINGS: &[&str] = &[
+    "install.dir",
+    "install.mode",
+    "update.channel",
+    "confirm.update",
+    "confirm.prune",
+    "network.timeout_seconds",
+    "network.proxy",
+    "retention.keep",
+    "desktop.per_version_entries",
+    "desktop.stable_entry",
+    "desktop.actions",
+    "desktop.name_format",
+    "desktop.comment",
+    "desktop.categories",
+    "desktop.desktop_shortcut",
+    "launch.profile",
+    "launch.check_updates",
+    "launch.on_update",
+    "launch.check_interval_minutes",
+    "launch.check_timeout_seconds",
+    "sandbox.binds",
+    "sandbox.ro_binds",
+    "sandbox.share_network",
+];
+
+/// Prefix of the environment variables that override settings.
+const ENV_PREFIX: &str = "OSU_HELPER_";
+
+/// User configuration, read from `config.toml` in the XDG config directory.
+///
+/// Every field has a default, so a missing file or a partial one is always valid. Settings are taken from, in order of
+/// precedence: command line flags, `OSU_HELPER_*` environment variables, the file, and the defaults.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub install: InstallConfig,
+    pub update: UpdateConfig,
+    pub confirm: ConfirmConfig,
+    pub network: NetworkConfig,
+    pub desktop: DesktopConfig,
+    pub retention: RetentionConfig,
+    pub launch: LaunchConfig,
+    pub sandbox: SandboxConfig,
+    /// Named launch profiles, as `[profiles.<name>]`.
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InstallConfig {
+    /// Base directory the versions are installed to, `~/.local/share/games/osu!` by default.
+    pub dir: Option<PathBuf>,
+    pub mode: InstallMode,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallMode {
+    /// Keep the AppImage as is, which needs FUSE to run.
+    #[default]
+    AppImage,
+    /// Unpack the AppImage into a directory and launch its `AppRun`, for systems without FUSE.
+    Extracted,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpdateConfig {
+    /// Channel updates follow. Tachyon follows prereleases as well as regular releases.
+    pub channel: Channel,
+}
+
+/// Whether commands ask before making changes, when --no-confirm isn't given.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfirmConfig {
+    pub update: bool,
+    pub prune: bool,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        Self {
+            update: true,
+            prune: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Seconds to wait for a connection, or for more data once connected, before giving up on a request.
+    pub timeout_seconds: u64,
+    /// Proxy requests go through, like `http://localhost:8080` or `socks5://localhost:1080`.
+    pub proxy: Option<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 30,
+            proxy: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DesktopConfig {
+    /// Create an `osu!-<tag>.desktop` entry for every installed version.
+    pub per_version_entries: bool,
+    /// Always keep an "osu!" desktop entry with a fixed file ID that follows the newest installed version.
+    ///
+    /// Pinning this entry to a dock survives updates, as the entry is only rewritten in place.
+    pub stable_entry: bool,
+    /// Write a single "osu!" entry that follows the newest installed version, with a right-click action for every
+    /// other installed version, instead of an entry per version.
+    pub actions: bool,
+    /// Name of the entries, where `{version}` is replaced by the tag. It's left out on the "osu!" entry.
+    pub name_format: String,
+    /// Translations of name_format, by locale, written as `Name[<locale>]`.
+    pub localized_names: BTreeMap<String, String>,
+    pub comment: String,
+    pub categories: Vec<String>,
+    /// Keys added to every entry, or replacing the generated ones. Values are written as is, so they must already be
+    /// escaped.
+    pub extra_keys: BTreeMap<String, String>,
+    /// Also put a shortcut to the active version in the desktop directory, usually `~/Desktop`.
+    pub desktop_shortcut: bool,
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        Self {
+            per_version_entries: true,
+            stable_entry: false,
+            actions: false,
+            name_format: "osu! {version}".to_owned(),
+            localized_names: BTreeMap::new(),
+            comment: "rhythm is just a *click* away!".to_owned(),
+            categories: vec!["Game".to_owned()],
+            extra_keys: BTreeMap::new(),
+            desktop_shortcut: false,
+        }
+    }
+}
+
+impl DesktopConfig {
+    /// Whether every version gets its own entry, which the actions mode replaces.
+    pub fn writes_per_version_entries(&self) -> bool {
+        self.per_version_entries && !self.actions
+    }
+
+    /// Whether the "osu!" entry, and the active version, follow the newest installed version.
+    pub fn follows_newest(&self) -> bool {
+        self.stable_entry || self.actions
+    }
+
+    /// Fills a name template for the version, or for the "osu!" entry if there's no version.
+    pub fn format_name(format: &str, version: Option<&str>) -> String {
+        let name = format.replace("{version}", version.unwrap_or_default());
+        name.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Number of newest versions to keep per channel after an update.
+    ///
+    /// When unset, an update only replaces the previously newest version.
+    pub keep: Option<NonZeroUsize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchConfig {
+    /// Profile used by the launch command and desktop entries when none is given.
+    pub profile: Option<String>,
+    /// Look for a newer release before starting the newest or the active version, like the updater on Windows.
+    ///
+    /// Desktop entries for those versions then go through the launch command of this tool.
+    pub check_updates: bool,
+    /// What to do when the check finds a newer release.
+    pub on_update: UpdateAction,
+    /// Minutes to reuse the result of the last check for, so launching the game often doesn't query GitHub each time.
+    pub check_interval_minutes: u64,
+    /// Seconds to wait for GitHub before giving up on the check and starting the installed version.
+    pub check_timeout_seconds: u64,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            check_updates: false,
+            on_update: UpdateAction::default(),
+            check_interval_minutes: 360,
+            check_timeout_seconds: 3,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateAction {
+    /// Show a desktop notification and start the installed version.
+    #[default]
+    Notify,
+    /// Ask whether to install the update first, when there's a terminal to ask in. Notifies otherwise.
+    Prompt,
+    /// Install the update, then start it.
+    Install,
+}
+
+/// Bubblewrap sandbox used by `launch --sandbox` and by profiles with `sandbox = true`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxConfig {
+    /// Extra paths the game may write to, as `path` or `source:destination`, where `~/` is the home directory.
+    pub binds: Vec<String>,
+    /// Extra paths the game may only read, in the same format as binds.
+    pub ro_binds: Vec<String>,
+    /// Let the game reach the network, which it needs to log in and download beatmaps.
+    pub share_network: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            binds: Vec::new(),
+            ro_binds: Vec::new(),
+            share_network: true,
+        }
+    }
+}
+
+/// How to start osu!, for things like `gamemoderun` or audio latency tweaks.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Environment variables set for the game.
+    pub env: BTreeMap<String, String>,
+    /// Commands the game is started through, outermost first, like `["gamemoderun", "mangohud"]`.
+    ///
+    /// Each one is split on whitespace, so wrappers can take their own arguments.
+    pub wrappers: Vec<String>,
+    /// Arguments passed to the game, before the ones given on the command line.
+    pub args: Vec<String>,
+    /// Shell commands run before the game starts. The game isn't started if one of them fails.
+    pub pre_launch: Vec<String>,
+    /// Shell commands run after the game exits.
+    pub post_launch: Vec<String>,
+    /// Run the game in the bubblewrap sandbox.
+    pub sandbox: bool,
+    /// Also write an "osu! (<name>)" desktop entry that launches the newest version with this profile.
+    pub desktop_entry: bool,
+}
+
+impl Config {
+    /// Returns the profile with the given name, or the default profile if there's no name.
+    pub fn get_profile<'a>(&'a self, name: Option<&'a str>) -> errors::Result<Option<(&'a str, &'a Profile)>> {
+        let name = match name.or(self.launch.profile.as_deref()) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some((name, profile))),
+            None => Err(Error::Descriptive(format!(
+                "Couldn't find a launch profile named {}.\nProfiles are defined as [profiles.<name>] in the config.",
+                name
+            ))),
+        }
+    }
+
+    pub fn path() -> PathBuf {
+        let config_dir = dirs::config_dir().expect("Couldn't find your config directory.");
+        config_dir.join("osu-helper-script").join("config.toml")
+    }
+
+    /// Reads the config file with the environment variable overrides applied, falling back to the defaults.
+    pub fn load() -> errors::Result<Self> {
+        let mut table = Self::load_file()?;
+        let overrides = get_env_overrides();
+        for (key, value) in &overrides {
+            set_value(&mut table, key, parse_value(value));
+        }
+
+        let describe = |e: &dyn std::fmt::Display| {
+            let path = Self::path();
+            let message = match overrides.is_empty() {
+                true => format!("Couldn't load the config at {}:\n{}", path.display(), e),
+                false => format!(
+                    "Couldn't load the config at {}, with the {}* environment variables applied:\n{}",
+                    path.display(),
+                    ENV_PREFIX,
+                    e
+                ),
+            };
+
+            Error::Descriptive(message)
+        };
+
+        let config = Self::from_table(table).map_err(|e| describe(&e))?;
+        config.validate().map_err(|e| describe(&e))?;
+        Ok(config)
+    }
+
+    /// Reads the config file as it is, without any defaults or overrides. It's empty if the file doesn't exist.
+    pub fn load_file() -> errors::Result<toml::Table> {
+        let path = Self::path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(toml::Table::new()),
+            Err(e) => {
+                return Err(Error::Io {
+                    source: e,
+                    context: Some(path.to_string_lossy().to_string()),
+                })
+            }
+        };
+
+        toml::from_str(&content)
+            .map_err(|e| Error::Descriptive(format!("Couldn't parse the config at {}:\n{}", path.display(), e)))
+    }
+
+    /// Writes a table back to the config file. Comments in the file are lost.
+    pub fn save_file(table: &toml::Table) -> errors::Result<()> {
+        let path = Self::path();
+        let parent = path.parent().expect("The config path should always have a parent");
+        fs::create_dir_all(parent).map_err(|e| Error::Io {
+            source: e,
+            context: Some(parent.to_string_lossy().to_string()),
+        })?;
+
+        let content = toml::to_string_pretty(table).expect("A table should always serialize");
+        fs::write(&path, content).map_err(|e| Error::Io {
+            source: e,
+            context: Some(path.to_string_lossy().to_string()),
+        })
+    }
+
+    pub fn from_table(table: toml::Table) -> Result<Self, toml::de::Error> {
+        Self::deserialize(toml::Value::Table(table))
+    }
+
+    pub fn to_table(&self) -> toml::Table {
+        toml::Table::try_from(self).expect("The config should always serialize")
+    }
+
+    /// Checks what can't be expressed by the types alone.
+    pub fn validate(&self) -> errors::Result<()> {
+        if self.install.dir.as_ref().is_some_and(|dir| !dir.is_absolute()) {
+            return Err(Error::Descriptive("install.dir has to be an absolute path.".to_owned()));
+        }
+        if self.network.timeout_seconds == 0 {
+            return Err(Error::Descriptive(
+                "network.timeout_seconds has to be at least 1.".to_owned(),
+            ));
+        }
+        if self.launch.check_timeout_seconds == 0 {
+            return Err(Error::Descriptive(
+                "launch.check_timeout_seconds has to be at least 1.".to_owned(),
+            ));
+        }
+
+        self.get_profile(None)?;
+        Ok(())
+    }
+}
+
+/// Returns the environment variable that overrides a setting, like `OSU_HELPER_INSTALL_DIR` for `install.dir`.
+pub fn get_env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// Returns the settings overridden by environment variables, with the raw values of the variables.
+pub fn get_env_overrides() -> Vec<(&'static str, String)> {
+    SETTINGS
+        .iter()
+        .filter_map(|key| Some((*key, env::var(get_env_var_name(key)).ok()?)))
+        .collect()
+}
+
+/// Parses a value from the command line or the environment as TOML, so `5`, `true` and `["Game"]` keep their types.
+///
+/// Anything that isn't valid TOML is taken as a string, so paths and names don't need to be quoted.
+pub fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+/// Looks up a dotted key, like `install.mode`, in a table.
+pub fn get_value<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (table_key, value_key) = key.split_once('.')?;
+    table.get(table_key)?.as_table()?.get(value_key)
+}
+
+/// Sets a dotted key, like `install.mode`, in a table, replacing its parent if that isn't a table.
+pub fn set_value(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let (table_key, value_key) = key.split_once('.').expect("Settings should always be in a table");
+    let parent = table
+        .entry(table_key)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if !parent.is_table() {
+        *parent = toml::Value::Table(toml::Table::new());
+    }
+
+    if let toml::Value::Table(parent) = parent {
+        parent.insert(value_key.to_owned(), value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{get_env_var_name, get_value, parse_value, set_value, Config, SETTINGS};
+
+    #[test]
+    fn values_are_parsed_as_toml_or_strings() {
+        assert_eq!(parse_value("5"), toml::Value::Integer(5));
+        assert_eq!(parse_value("false"), toml::Value::Boolean(false));
+        assert_eq!(parse_value("\"a b\""), toml::Value::String("a b".to_owned()));
+        assert_eq!(
+            parse_value("/home/me/osu!"),
+            toml::Value::String("/home/me/osu!".to_owned())
+        );
+        assert_eq!(parse_value("2023.617.0"), toml::Value::String("2023.617.0".to_owned()));
+    }
+
+    #[test]
+    fn every_setting_can_be_set() {
+        assert_eq!(get_env_var_name("install.dir"), "OSU_HELPER_INSTALL_DIR");
+
+        let defaults = Config::default().to_table();
+        for key in SETTINGS {
+            // Settings without a default are left out, so they get a value of the right type.
+            let value = get_value(&defaults, key).cloned().unwrap_or_else(|| match *key {
+                "retention.keep" => toml::Value::Integer(2),
+                _ => toml::Value::String("osu".to_owned()),
+            });
+
+            let mut table = toml::Table::new();
+            set_value(&mut table, key, value);
+            assert!(Config::from_table(table).is_ok(), "{} can't be set", key);
+        }
+    }
+
+    #[test]
+    fn keeping_no_versions_is_rejected() {
+        let mut table = toml::Table::new();
+        set_value(&mut table, "retention.keep", toml::Value::Integer(0));
+        assert!(Config::from_table(table).is_err());
+    }
+
+    #[test]
+    fn zero_timeouts_are_rejected() {
+        for key in ["network.timeout_seconds", "launch.check_timeout_seconds"] {
+            let mut table = toml::Table::new();
+            set_value(&mut table, key, toml::Value::Integer(0));
+            assert!(
+                Config::from_table(table).unwrap().validate().is_err(),
+                "{} can be 0",
+                key
+            );
+        }
+    }
+}
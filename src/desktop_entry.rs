@@ -0,0 +1,587 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Name of the group every desktop entry starts with.
+pub const MAIN_GROUP: &str = "[Desktop Entry]";
+
+/// Keys defined by the Desktop Entry Specification. Anything else has to be prefixed with `X-`.
+const KNOWN_KEYS: [&str; 24] = [
+    "Type",
+    "Version",
+    "Name",
+    "GenericName",
+    "NoDisplay",
+    "Comment",
+    "Icon",
+    "Hidden",
+    "OnlyShowIn",
+    "NotShowIn",
+    "DBusActivatable",
+    "TryExec",
+    "Exec",
+    "Path",
+    "Terminal",
+    "Actions",
+    "MimeType",
+    "Categories",
+    "Implements",
+    "Keywords",
+    "StartupNotify",
+    "StartupWMClass",
+    "URL",
+    "PrefersNonDefaultGPU",
+];
+
+const BOOLEAN_KEYS: [&str; 7] = [
+    "NoDisplay",
+    "Hidden",
+    "DBusActivatable",
+    "Terminal",
+    "StartupNotify",
+    "PrefersNonDefaultGPU",
+    "SingleMainWindow",
+];
+
+/// Main categories of the Desktop Menu Specification, at least one of which should be listed.
+const MAIN_CATEGORIES: [&str; 13] = [
+    "AudioVideo",
+    "Audio",
+    "Video",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+/// Characters that force an `Exec` argument to be quoted.
+const RESERVED_EXEC_CHARS: &str = " \t\n\"'\\><~|&;$*?#()`";
+
+/// Line of a desktop entry file, kept as is so rewriting an entry doesn't lose comments or unknown groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Group(String),
+    Entry { key: String, value: String },
+    Other(String),
+}
+
+/// A desktop entry file, following the Desktop Entry Specification.
+///
+/// Values are stored escaped, exactly as they appear in the file. The setters take care of escaping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    lines: Vec<Line>,
+}
+
+impl Default for DesktopEntry {
+    fn default() -> Self {
+        Self {
+            lines: vec![Line::Group(MAIN_GROUP.to_owned())],
+        }
+    }
+}
+
+impl DesktopEntry {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                if line.starts_with('[') {
+                    return Line::Group(line.trim_end().to_owned());
+                }
+
+                match line.split_once('=') {
+                    Some((key, value)) if !line.starts_with('#') => Line::Entry {
+                        key: key.trim().to_owned(),
+                        value: value.trim_start().to_owned(),
+                    },
+                    _ => Line::Other(line.to_owned()),
+                }
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Returns the escaped value of a key in the main group.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.group_range(MAIN_GROUP).and_then(|range| {
+            self.lines[range].iter().find_map(|line| match line {
+                Line::Entry { key: k, value } if k == key => Some(value.as_str()),
+                _ => None,
+            })
+        })
+    }
+
+    /// Returns the unescaped value of a string key in the main group.
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        self.get(key).map(unescape)
+    }
+
+    /// Returns the arguments of the `Exec` key of the main group, starting with the program.
+    pub fn get_exec_args(&self) -> Option<Vec<String>> {
+        self.get_string("Exec").and_then(|exec| split_exec(&exec).ok())
+    }
+
+    /// Sets an already escaped value in the main group, replacing the existing one in place.
+    pub fn set_raw(&mut self, key: &str, value: impl Into<String>) {
+        self.set_raw_in(MAIN_GROUP, key, value);
+    }
+
+    pub fn set_raw_in(&mut self, group: &str, key: &str, value: impl Into<String>) {
+        let range = match self.group_range(group) {
+            Some(range) => range,
+            None => {
+                if !self.lines.is_empty() {
+                    self.lines.push(Line::Other(String::new()));
+                }
+                self.lines.push(Line::Group(group.to_owned()));
+                self.lines.len()..self.lines.len()
+            }
+        };
+
+        let value = value.into();
+        for line in &mut self.lines[range.clone()] {
+            if let Line::Entry { key: k, value: v } = line {
+                if k == key {
+                    *v = value;
+                    return;
+                }
+            }
+        }
+
+        // New keys go after the last entry, so trailing blank lines stay between the groups.
+        let position = self.lines[range.clone()]
+            .iter()
+            .rposition(|line| matches!(line, Line::Entry { .. }))
+            .map_or(range.start, |index| range.start + index + 1);
+
+        self.lines.insert(
+            position,
+            Line::Entry {
+                key: key.to_owned(),
+                value,
+            },
+        );
+    }
+
+    pub fn set_string(&mut self, key: &str, value: &str) {
+        self.set_raw(key, escape(value));
+    }
+
+    pub fn set_list(&mut self, key: &str, values: &[&str]) {
+        self.set_raw(key, escape_list(values));
+    }
+
+    /// Removes every group whose header matches the predicate, along with its keys.
+    pub fn remove_groups_where(&mut self, predicate: impl Fn(&str) -> bool) {
+        let mut in_removed_group = false;
+        self.lines.retain(|line| {
+            if let Line::Group(name) = line {
+                in_removed_group = predicate(name);
+            }
+
+            !in_removed_group
+        });
+
+        // Drop the blank lines that separated the removed groups.
+        while matches!(self.lines.last(), Some(Line::Other(line)) if line.trim().is_empty()) {
+            self.lines.pop();
+        }
+    }
+
+    /// Removes keys of the main group matching the predicate, like every localized `Name[xx]`.
+    pub fn remove_where(&mut self, predicate: impl Fn(&str) -> bool) {
+        let range = match self.group_range(MAIN_GROUP) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let mut index = range.start;
+        let mut end = range.end;
+        while index < end {
+            match &self.lines[index] {
+                Line::Entry { key, .. } if predicate(key) => {
+                    self.lines.remove(index);
+                    end -= 1;
+                }
+                _ => index += 1,
+            }
+        }
+    }
+
+    /// Checks the entry the same way `desktop-file-validate` does, for the parts this tool relies on.
+    ///
+    /// Returns a description of every problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        match self.lines.iter().find(|line| !matches!(line, Line::Other(_))) {
+            Some(Line::Group(group)) if group == MAIN_GROUP => {}
+            _ => problems.push(format!("The first group must be {}.", MAIN_GROUP)),
+        }
+
+        let mut group = String::new();
+        let mut seen_keys = Vec::new();
+        for line in &self.lines {
+            match line {
+                Line::Group(name) => {
+                    if !name.ends_with(']') || name[1..name.len() - 1].contains(['[', ']']) {
+                        problems.push(format!("{} isn't a valid group header.", name));
+                    }
+
+                    group = name.clone();
+                    seen_keys.clear();
+                }
+                Line::Entry { key, value } => {
+                    if seen_keys.contains(key) {
+                        problems.push(format!("{} is set more than once in {}.", key, group));
+                    }
+                    seen_keys.push(key.clone());
+
+                    problems.extend(validate_entry(key, value));
+                }
+                Line::Other(line) => {
+                    if !line.trim().is_empty() && !line.starts_with('#') {
+                        problems.push(format!("\"{}\" isn't a key, a group or a comment.", line));
+                    }
+                }
+            }
+        }
+
+        match self.get("Type") {
+            None => problems.push("The Type key is required.".to_owned()),
+            Some("Application") if self.get("Exec").is_none() => {
+                problems.push("The Exec key is required for applications.".to_owned())
+            }
+            _ => {}
+        }
+
+        if self.get("Name").is_none() {
+            problems.push("The Name key is required.".to_owned());
+        }
+
+        for action in self.get("Actions").map(split_list).unwrap_or_default() {
+            let group = format!("[Desktop Action {}]", action);
+            let has_name = self.group_range(&group).is_some_and(|range| {
+                self.lines[range]
+                    .iter()
+                    .any(|line| matches!(line, Line::Entry { key, .. } if key == "Name"))
+            });
+
+            if !has_name {
+                problems.push(format!("The {} action needs a {} group with a Name.", action, group));
+            }
+        }
+
+        if let Some(categories) = self.get("Categories") {
+            if !split_list(categories)
+                .iter()
+                .any(|c| MAIN_CATEGORIES.contains(&c.as_str()))
+            {
+                problems.push(format!(
+                    "Categories should contain one of the main categories: {}.",
+                    MAIN_CATEGORIES.join(", ")
+                ));
+            }
+        }
+
+        problems
+    }
+
+    fn group_range(&self, group: &str) -> Option<std::ops::Range<usize>> {
+        let start = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Group(name) if name == group))?
+            + 1;
+        let end = self.lines[start..]
+            .iter()
+            .position(|line| matches!(line, Line::Group(_)))
+            .map_or(self.lines.len(), |index| start + index);
+
+        Some(start..end)
+    }
+}
+
+impl Display for DesktopEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Group(name) => writeln!(f, "{}", name)?,
+                Line::Entry { key, value } => writeln!(f, "{}={}", key, value)?,
+                Line::Other(line) => writeln!(f, "{}", line)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_entry(key: &str, value: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let (base_key, locale) = match key.split_once('[') {
+        Some((base_key, locale)) => (base_key, locale.strip_suffix(']')),
+        None => (key, Some("")),
+    };
+
+    if base_key.is_empty() || !base_key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        problems.push(format!("{} isn't a valid key name.", key));
+    }
+
+    if locale.is_none() || locale.is_some_and(|locale| locale.contains(['[', ']', ' '])) {
+        problems.push(format!("{} has an invalid locale.", key));
+    }
+
+    if !KNOWN_KEYS.contains(&base_key) && !BOOLEAN_KEYS.contains(&base_key) && !base_key.starts_with("X-") {
+        problems.push(format!("{} isn't a standard key, extensions must start with X-.", key));
+    }
+
+    if BOOLEAN_KEYS.contains(&base_key) && value != "true" && value != "false" {
+        problems.push(format!("{} must be either true or false, not {}.", key, value));
+    }
+
+    if base_key == "Version" && !["1.0", "1.1", "1.5"].contains(&value) {
+        problems.push(format!("{} isn't a known specification version.", value));
+    }
+
+    if [
+        "Categories",
+        "MimeType",
+        "Actions",
+        "Keywords",
+        "OnlyShowIn",
+        "NotShowIn",
+    ]
+    .contains(&base_key)
+        && !value.ends_with(';')
+    {
+        problems.push(format!("The {} list should end with a semicolon.", key));
+    }
+
+    if base_key == "Exec" {
+        match split_exec(&unescape(value)) {
+            Ok(_) => problems.extend(validate_field_codes(value)),
+            Err(e) => problems.push(format!("Exec is invalid: {}.", e)),
+        }
+    }
+
+    problems
+}
+
+fn validate_field_codes(exec: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut file_codes = 0;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        match chars.next() {
+            Some('f' | 'F' | 'u' | 'U') => file_codes += 1,
+            Some('i' | 'c' | 'k' | '%') => {}
+            Some(code) => problems.push(format!("Exec contains an unknown field code %{}.", code)),
+            None => problems.push("Exec ends with a lone %.".to_owned()),
+        }
+    }
+
+    if file_codes > 1 {
+        problems.push("Exec may only contain one of %f, %F, %u and %U.".to_owned());
+    }
+
+    problems
+}
+
+/// Escapes a value of type string, as described in the "Possible value types" section of the specification.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ' ' if index == 0 => escaped.push_str("\\s"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+pub fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Escapes every value and joins them into a semicolon terminated list.
+pub fn escape_list(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| format!("{};", escape(value).replace(';', "\\;")))
+        .collect()
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Builds the escaped value of an `Exec` key.
+///
+/// Arguments with reserved characters are quoted, and literal percent signs are doubled so they aren't mistaken for
+/// field codes. The result is then escaped like any other string.
+pub fn format_exec(program: &Path, args: &[&str], field_codes: &[&str]) -> String {
+    let program = program.to_string_lossy();
+    let mut parts = std::iter::once(program.as_ref())
+        .chain(args.iter().copied())
+        .map(quote_exec_arg)
+        .collect::<Vec<String>>();
+    parts.extend(field_codes.iter().map(|code| code.to_string()));
+
+    escape(&parts.join(" "))
+}
+
+fn quote_exec_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(|c| RESERVED_EXEC_CHARS.contains(c)) {
+        return arg;
+    }
+
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Splits an unescaped `Exec` value into its arguments, undoing the quoting and the doubled percent signs.
+///
+/// Field codes are kept as separate arguments.
+pub fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if matches!(c, '"' | '`' | '$' | '\\') => current.push(c),
+                            _ => return Err("a quoted argument has an invalid escape sequence".to_owned()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("a quoted argument isn't closed".to_owned()),
+                    }
+                }
+            }
+            ' ' | '\t' | '\n' => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c if RESERVED_EXEC_CHARS.contains(c) => {
+                return Err(format!("the reserved character {} must be quoted", c));
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args.into_iter().map(|arg| arg.replace("%%", "%")).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::DesktopEntry;
+
+    #[test]
+    fn exec_is_quoted_and_escaped() {
+        let exec = super::format_exec(Path::new("/home/user/my games/osu!/100%.AppImage"), &[], &["%U"]);
+        assert_eq!(exec, r#""/home/user/my games/osu!/100%%.AppImage" %U"#);
+
+        let exec = super::format_exec(Path::new("/opt/osu"), &["C:\\path", "$HOME"], &[]);
+        assert_eq!(exec, r#"/opt/osu "C:\\\\path" "\\$HOME""#);
+
+        let args = super::split_exec(&super::unescape(&exec)).unwrap();
+        assert_eq!(args, vec!["/opt/osu", "C:\\path", "$HOME"]);
+    }
+
+    #[test]
+    fn rewriting_keeps_unmanaged_lines() {
+        let mut entry = DesktopEntry::parse("# pinned\n[Desktop Entry]\nName=old\nX-Custom=1\n\n[Other]\nName=x\n");
+        entry.set_string("Name", "osu!");
+        entry.set_string("Comment", " leading space");
+
+        assert_eq!(
+            entry.to_string(),
+            "# pinned\n[Desktop Entry]\nName=osu!\nX-Custom=1\nComment=\\sleading space\n\n[Other]\nName=x\n"
+        );
+    }
+
+    #[test]
+    fn validation_finds_problems() {
+        let mut entry = DesktopEntry::default();
+        entry.set_raw("Type", "Application");
+        entry.set_string("Name", "osu!");
+        entry.set_raw("Exec", super::format_exec(Path::new("/opt/osu"), &[], &["%U"]));
+        entry.set_list("Categories", &["Game"]);
+        assert!(entry.validate().is_empty());
+
+        entry.set_raw("Terminal", "yes");
+        entry.set_raw("Custom", "1");
+        entry.set_raw("Exec", "/opt/my osu %Q");
+        assert_eq!(entry.validate().len(), 3);
+    }
+}
@@ -1,58 +1,72 @@
-use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{
-        GITHUB_ICON_URL, GITHUB_LATEST_RELEASE_URL, GITHUB_RELEASES_URL, GITHUB_RELEASE_TAG_URL,
-        USER_AGENT,
-    },
-    errors::{self, Error},
+    constants::{GITHUB_LATEST_RELEASE_URL, GITHUB_RELEASES_URL, GITHUB_RELEASE_TAG_URL, USER_AGENT},
     net,
 };
 
 pub fn get_releases() -> Result<Vec<GithubRelease>, Box<ureq::Error>> {
-    net::box_and_deserialize::<Vec<GithubRelease>>(
-        ureq::get(GITHUB_RELEASES_URL).set("User-Agent", USER_AGENT),
-    )
+    net::box_and_deserialize::<Vec<GithubRelease>>(net::agent().get(GITHUB_RELEASES_URL).set("User-Agent", USER_AGENT))
 }
 
 pub fn get_release(tag: &str) -> Result<GithubRelease, Box<ureq::Error>> {
     net::box_and_deserialize::<GithubRelease>(
-        ureq::get(&format!("{}/{}", GITHUB_RELEASE_TAG_URL, tag)).set("User-Agent", USER_AGENT),
+        net::agent()
+            .get(&format!("{}/{}", GITHUB_RELEASE_TAG_URL, tag))
+            .set("User-Agent", USER_AGENT),
     )
 }
 
-pub fn get_latest_release() -> Result<GithubRelease, Box<ureq::Error>> {
-    net::box_and_deserialize::<GithubRelease>(
-        ureq::get(GITHUB_LATEST_RELEASE_URL).set("User-Agent", USER_AGENT),
-    )
-}
-
-pub fn get_icon() -> errors::Result<Vec<u8>> {
-    let response = net::box_request(ureq::get(GITHUB_ICON_URL).set("User-Agent", USER_AGENT))?;
-    let size = match response.header("Content-Length").unwrap().parse::<u64>() {
-        Ok(size) => size,
-        Err(_) => return Err(Error::Descriptive("Couldn't parse icon size".into())),
+/// Returns the newest release of the channel, giving up once the timeout is over if there is one.
+///
+/// GitHub's latest release is never a prerelease, so releases for tachyon are taken from the release list instead,
+/// where they come newest first.
+pub fn get_latest_release(channel: Channel, timeout: Option<Duration>) -> Result<GithubRelease, Box<ureq::Error>> {
+    let url = match channel {
+        Channel::Lazer => GITHUB_LATEST_RELEASE_URL,
+        Channel::Tachyon => GITHUB_RELEASES_URL,
     };
 
-    let mut icon = Vec::with_capacity(size as usize);
-    response.into_reader().read_to_end(&mut icon)?;
+    let mut request = net::agent().get(url).set("User-Agent", USER_AGENT);
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
 
-    Ok(icon)
+    match channel {
+        Channel::Lazer => net::box_and_deserialize::<GithubRelease>(request),
+        Channel::Tachyon => {
+            let releases = net::box_and_deserialize::<Vec<GithubRelease>>(request)?;
+            match releases.into_iter().next() {
+                Some(release) => Ok(release),
+                None => get_latest_release(Channel::Lazer, timeout),
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GithubRelease {
-    pub id: u64,
     pub tag_name: String,
     pub prerelease: bool,
+    pub published_at: Option<String>,
+    pub html_url: String,
     pub assets: Vec<GithubReleaseAsset>,
 }
 
 impl GithubRelease {
+    pub fn channel(&self) -> Channel {
+        if self.prerelease {
+            Channel::Tachyon
+        } else {
+            Channel::Lazer
+        }
+    }
+
     pub fn get_app_image_asset(&self) -> Option<&GithubReleaseAsset> {
-        self.assets
-            .iter()
-            .find(|asset| asset.name.ends_with(".AppImage"))
+        self.assets.iter().find(|asset| asset.name.ends_with(".AppImage"))
     }
 }
 
@@ -62,3 +76,32 @@ pub struct GithubReleaseAsset {
     pub size: u64,
     pub browser_download_url: String,
 }
+
+/// Release channel of a build, derived from the prerelease flag of its GitHub release.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Lazer,
+    Tachyon,
+}
+
+impl Channel {
+    /// Infers the channel from a tag alone, for binaries that don't come with their GitHub release.
+    pub fn from_tag(tag: &str) -> Self {
+        if tag.ends_with("-tachyon") {
+            Self::Tachyon
+        } else {
+            Self::Lazer
+        }
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lazer => write!(f, "lazer"),
+            Self::Tachyon => write!(f, "tachyon"),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::errors::{self, Error};
+use crate::github::{self, GithubRelease};
+use crate::local;
+use crate::manifest::{Manifest, UpdateRecord};
+
+/// Record of a multi-step operation, kept in the install_dir until every step is done.
+///
+/// Each step is marked as done right after it completes, so an interrupted operation can be
+/// completed or rolled back on the next run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    pub operation: String,
+    pub steps: Vec<JournalStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalStep {
+    pub action: Action,
+    pub done: bool,
+}
+
+/// A single step of an operation. Every action and its undo must be safe to run more than once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Install { tag: String },
+    SetActive { tag: String, previous: Option<String> },
+    Remove { tag: String },
+    RecordUpdate { record: UpdateRecord },
+    UpdateDesktopDatabase,
+}
+
+impl Action {
+    fn describe(&self) -> String {
+        match self {
+            Self::Install { tag } => format!("install {}", tag),
+            Self::SetActive { tag, .. } => format!("mark {} as active", tag),
+            Self::Remove { tag } => format!("remove {}", tag),
+            Self::RecordUpdate { record } => format!("record the update from {} to {}", record.from, record.to),
+            Self::UpdateDesktopDatabase => "update the desktop database".to_owned(),
+        }
+    }
+}
+
+impl Journal {
+    pub fn path(install_dir: &Path) -> PathBuf {
+        install_dir.join("journal.json")
+    }
+
+    /// Starts a new operation and writes it to the install_dir.
+    ///
+    /// Fails if there's an unfinished operation, as its journal is the only record of what's left to do.
+    pub fn begin(install_dir: &Path, operation: &str, actions: Vec<Action>) -> errors::Result<Self> {
+        if let Some(unfinished) = Self::load(install_dir)? {
+            return Err(Error::Descriptive(format!(
+                "Can't start the {} while a previous {} is unfinished.\n\
+                Run: osu-helper-script {} to complete it or roll it back first.",
+                operation, unfinished.operation, operation
+            )));
+        }
+
+        let journal = Self {
+            operation: operation.to_owned(),
+            steps: actions
+                .into_iter()
+                .map(|action| JournalStep { action, done: false })
+                .collect(),
+        };
+
+        journal.save(install_dir)?;
+        Ok(journal)
+    }
+
+    /// Reads the journal of an unfinished operation, if there's one.
+    pub fn load(install_dir: &Path) -> errors::Result<Option<Self>> {
+        let path = Self::path(install_dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::Io {
+                    source: e,
+                    context: Some(path.to_string_lossy().to_string()),
+                })
+            }
+        };
+
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::Descriptive(format!("Couldn't parse the journal at {}:\n{}", path.display(), e)))
+    }
+
+    fn save(&self, install_dir: &Path) -> errors::Result<()> {
+        let path = Self::path(install_dir);
+        let temp_path = path.with_extension("json.tmp");
+        let content = serde_json::to_string_pretty(self).expect("journal should always be serializable");
+
+        // Written through a rename, so a crash never leaves a torn journal behind.
+        let write = || -> io::Result<()> {
+            let mut file = fs::File::create(&temp_path)?;
+            io::Write::write_all(&mut file, content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&temp_path, &path)
+        };
+
+        write().map_err(|e| Error::Io {
+            source: e,
+            context: Some(path.to_string_lossy().to_string()),
+        })
+    }
+
+    /// Performs every step that isn't done yet, in order.
+    ///
+    /// The known_release is used for install steps of the same tag, instead of fetching it again.
+    pub fn run(
+        &mut self,
+        local_data_dir: &Path,
+        install_dir: &Path,
+        config: &Config,
+        known_release: Option<&GithubRelease>,
+    ) -> errors::Result<()> {
+        for index in 0..self.steps.len() {
+            if self.steps[index].done {
+                continue;
+            }
+
+            let action = self.steps[index].action.clone();
+            perform(&action, local_data_dir, install_dir, config, known_release)?;
+
+            self.steps[index].done = true;
+            self.save(install_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the journal, marking the operation as finished.
+    pub fn finish(self, install_dir: &Path) -> errors::Result<()> {
+        let path = Self::path(install_dir);
+        fs::remove_file(&path).map_err(|e| Error::Io {
+            source: e,
+            context: Some(path.to_string_lossy().to_string()),
+        })
+    }
+
+    /// Undoes every step that was done, and the one that might have been in progress, in reverse order.
+    pub fn roll_back(self, local_data_dir: &Path, install_dir: &Path, config: &Config) -> errors::Result<()> {
+        let in_progress = self
+            .steps
+            .iter()
+            .position(|step| !step.done)
+            .unwrap_or(self.steps.len());
+        let attempted = self.steps.iter().take(in_progress + 1).collect::<Vec<&JournalStep>>();
+
+        for step in attempted.iter().rev() {
+            undo(&step.action, local_data_dir, install_dir, config)?;
+        }
+
+        local::update_desktop_database(local_data_dir)?;
+        self.finish(install_dir)
+    }
+
+    pub fn describe(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| {
+                let state = if step.done { "done" } else { "pending" };
+                format!("  - {} ({})", step.action.describe(), state)
+            })
+            .collect::<Vec<String>>();
+
+        format!("{}:\n{}", self.operation, steps.join("\n"))
+    }
+}
+
+fn perform(
+    action: &Action,
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    known_release: Option<&GithubRelease>,
+) -> errors::Result<()> {
+    match action {
+        Action::Install { tag } => match known_release {
+            Some(release) if &release.tag_name == tag => {
+                local::initialize_binary(local_data_dir, install_dir, config, release)
+            }
+            _ => local::initialize_binary(local_data_dir, install_dir, config, &fetch_release(tag)?),
+        },
+        Action::SetActive { tag, .. } => local::set_active_binary(local_data_dir, install_dir, config, tag),
+        Action::Remove { tag } => local::remove_binary(local_data_dir, install_dir, config, tag),
+        Action::RecordUpdate { record } => {
+            let mut manifest = Manifest::load(install_dir)?;
+            manifest.last_update = Some(record.clone());
+            manifest.save(install_dir)?;
+
+            local::remove_stale_realm_snapshots(install_dir, record.realm_snapshot.as_deref())
+        }
+        Action::UpdateDesktopDatabase => local::update_desktop_database(local_data_dir),
+    }
+}
+
+fn undo(action: &Action, local_data_dir: &Path, install_dir: &Path, config: &Config) -> errors::Result<()> {
+    let installed_tags = local::get_local_release_tags(install_dir)?;
+
+    match action {
+        Action::Install { tag } => {
+            if installed_tags.contains(tag) {
+                local::remove_binary(local_data_dir, install_dir, config, tag)?;
+            }
+
+            let mut manifest = Manifest::load(install_dir)?;
+            if manifest.last_update.as_ref().is_some_and(|record| &record.to == tag) {
+                manifest.last_update = None;
+                manifest.save(install_dir)?;
+            }
+
+            Ok(())
+        }
+        Action::SetActive { previous, .. } => match previous {
+            Some(previous) if installed_tags.contains(previous) => {
+                local::set_active_binary(local_data_dir, install_dir, config, previous)
+            }
+            Some(_) => Ok(()),
+            None => local::clear_active_binary(local_data_dir, install_dir),
+        },
+        Action::Remove { tag } => {
+            if installed_tags.contains(tag) {
+                return Ok(());
+            }
+
+            local::initialize_binary(local_data_dir, install_dir, config, &fetch_release(tag)?)
+        }
+        Action::RecordUpdate { record } => {
+            let mut manifest = Manifest::load(install_dir)?;
+            if manifest
+                .last_update
+                .as_ref()
+                .is_some_and(|last_update| last_update.to == record.to)
+            {
+                manifest.last_update = None;
+                manifest.save(install_dir)?;
+            }
+
+            Ok(())
+        }
+        Action::UpdateDesktopDatabase => Ok(()),
+    }
+}
+
+fn fetch_release(tag: &str) -> errors::Result<GithubRelease> {
+    github::get_release(tag).map_err(|e| {
+        if let ureq::Error::Status(404, _) = *e {
+            return Error::Descriptive(format!("Couldn't find a release with the tag {}", tag));
+        }
+
+        Error::from(e)
+    })
+}
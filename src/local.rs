@@ -1,11 +1,60 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
+use indicatif::HumanBytes;
+use sha2::{Digest, Sha256};
+
+use crate::appimage::{self, AppImage};
+use crate::cleanup::{PendingFile, PendingRestore};
+use crate::config::{Config, DesktopConfig, InstallMode};
+use crate::desktop_entry::{self, DesktopEntry};
 use crate::errors::{self, ignore_io_not_found, Error};
-use crate::github::{self, GithubRelease};
+use crate::github::{Channel, GithubRelease, GithubReleaseAsset};
+use crate::manifest::{Manifest, VersionEntry};
 use crate::net;
 
-/// Lists all the releases available in the install_dir.
+/// Name of the symlink in the install_dir that points to the active binary.
+pub const ACTIVE_LINK_NAME: &str = "osu.AppImage";
+
+/// Name of the desktop entry that launches the active binary through [ACTIVE_LINK_NAME].
+pub const ACTIVE_DESKTOP_ENTRY_NAME: &str = "osu!.desktop";
+
+/// Name the osu! icon is installed under in the hicolor icon theme, and referenced by from desktop entries.
+pub const ICON_NAME: &str = "osu!";
+
+/// MIME types osu! can open, registered on every desktop entry.
+pub const MIME_TYPES: [&str; 4] = [
+    "application/x-osu-beatmap-archive",
+    "application/x-osu-skin-archive",
+    "application/x-osu-replay",
+    "x-scheme-handler/osu",
+];
+
+/// shared-mime-info definitions of the osu! file formats, which aren't known to the system database.
+const MIME_PACKAGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<mime-info xmlns="http://www.freedesktop.org/standards/shared-mime-info">
+  <mime-type type="application/x-osu-beatmap-archive">
+    <comment>osu! beatmap archive</comment>
+    <sub-class-of type="application/zip"/>
+    <glob pattern="*.osz"/>
+  </mime-type>
+  <mime-type type="application/x-osu-skin-archive">
+    <comment>osu! skin archive</comment>
+    <sub-class-of type="application/zip"/>
+    <glob pattern="*.osk"/>
+  </mime-type>
+  <mime-type type="application/x-osu-replay">
+    <comment>osu! replay</comment>
+    <glob pattern="*.osr"/>
+  </mime-type>
+</mime-info>
+"#;
+
+/// Lists all the releases available in the install_dir, either as an AppImage or as an extracted directory.
 ///
 /// Returned vector is sorted in descending order.
 ///
@@ -20,11 +69,17 @@ pub fn get_local_release_tags(install_dir: &Path) -> io::Result<Vec<String>> {
                 let entry = entry.ok()?;
                 let path = entry.path();
 
+                let name = path.file_name().map(|name| name.to_string_lossy().to_string())?;
                 if path.is_dir() {
+                    // Leftovers of an interrupted extraction aren't installs.
+                    let is_extracted = path.join("AppRun").symlink_metadata().is_ok();
+                    return (is_extracted && !name.ends_with(".part") && !name.ends_with(".old")).then_some(name);
+                }
+
+                if name == ACTIVE_LINK_NAME {
                     return None;
                 }
 
-                let name = path.file_name().map(|name| name.to_string_lossy().to_string())?;
                 if name.ends_with(".AppImage") {
                     Some(name.replace(".AppImage", ""))
                 } else {
@@ -77,8 +132,511 @@ pub fn cmp_version_tag_ltr(left: &str, right: &str) -> std::cmp::Ordering {
     left.cmp(&right)
 }
 
+/// Lists the installed versions that fall outside of the retention policy.
+///
+/// Returned vector is sorted in descending order.
+pub fn get_prunable_release_tags(install_dir: &Path, keep: NonZeroUsize) -> errors::Result<Vec<String>> {
+    let installed_tags = get_local_release_tags(install_dir)?;
+    let manifest = Manifest::load(install_dir)?;
+    let active_tag = get_active_tag(install_dir)?;
+
+    let channels = manifest
+        .versions
+        .iter()
+        .map(|(tag, entry)| (tag.as_str(), entry.channel))
+        .collect::<HashMap<&str, Channel>>();
+
+    let mut protected = manifest.pinned.iter().map(String::as_str).collect::<Vec<&str>>();
+    if let Some(active_tag) = &active_tag {
+        protected.push(active_tag);
+    }
+
+    Ok(select_prunable_tags(&installed_tags, &channels, &protected, keep.get()))
+}
+
+/// Lists the installed versions that will fall outside of the retention policy once `new_tag` is installed and
+/// marked as active, so an update can plan its removals before installing anything.
+///
+/// Returned vector is sorted in descending order, and never contains `new_tag`.
+pub fn get_prunable_release_tags_after_install(
+    install_dir: &Path,
+    keep: NonZeroUsize,
+    new_tag: &str,
+    channel: Channel,
+) -> errors::Result<Vec<String>> {
+    let mut installed_tags = get_local_release_tags(install_dir)?;
+    if !installed_tags.iter().any(|tag| tag == new_tag) {
+        installed_tags.push(new_tag.to_owned());
+    }
+    let installed_tags = sort_version_tags_desc(installed_tags);
+    let manifest = Manifest::load(install_dir)?;
+
+    let mut channels = manifest
+        .versions
+        .iter()
+        .map(|(tag, entry)| (tag.as_str(), entry.channel))
+        .collect::<HashMap<&str, Channel>>();
+    channels.insert(new_tag, channel);
+
+    let mut protected = manifest.pinned.iter().map(String::as_str).collect::<Vec<&str>>();
+    protected.push(new_tag);
+
+    Ok(select_prunable_tags(&installed_tags, &channels, &protected, keep.get()))
+}
+
+/// Picks every tag beyond the `keep` newest of its channel, skipping the protected ones.
+///
+/// Protected tags don't count towards `keep`. Tags without a known channel are grouped together.
+fn select_prunable_tags(
+    sorted_tags: &[String],
+    channels: &HashMap<&str, Channel>,
+    protected: &[&str],
+    keep: usize,
+) -> Vec<String> {
+    let mut kept_per_channel = HashMap::<Option<Channel>, usize>::new();
+
+    sorted_tags
+        .iter()
+        .filter(|tag| {
+            if protected.contains(&tag.as_str()) {
+                return false;
+            }
+
+            let kept = kept_per_channel.entry(channels.get(tag.as_str()).copied()).or_default();
+            if *kept < keep {
+                *kept += 1;
+                false
+            } else {
+                true
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Extra space required on top of a download, so the filesystem isn't filled to the brim.
+const FREE_SPACE_MARGIN: u64 = 128 * 1024 * 1024;
+
+/// Makes sure the filesystem of each directory has room for `required` bytes plus a margin.
+///
+/// The error lists the space used by installed versions, so the user knows what they can clean up.
+pub fn ensure_free_space(install_dir: &Path, dirs: &[&Path], required: u64) -> errors::Result<()> {
+    let required = required + FREE_SPACE_MARGIN;
+
+    for dir in dirs {
+        let available = match get_available_space(dir)? {
+            Some(available) => available,
+            None => continue,
+        };
+
+        if available >= required {
+            continue;
+        }
+
+        let mut message = format!(
+            "Not enough free space in {}: {} required, but only {} available.",
+            dir.display(),
+            HumanBytes(required),
+            HumanBytes(available)
+        );
+
+        let installed_tags = get_local_release_tags(install_dir)?;
+        if !installed_tags.is_empty() {
+            message.push_str("\nInstalled versions currently use:");
+            for tag in installed_tags {
+                let size = get_install_size(install_dir, &tag)?;
+                message.push_str(&format!("\n  {:<14} {:>12}", tag, HumanBytes(size).to_string()));
+            }
+
+            message.push_str("\nUse the prune or remove commands to free up some space.");
+        }
+
+        return Err(Error::Descriptive(message));
+    }
+
+    Ok(())
+}
+
+/// Returns the space taken by an installed version, whether it's an AppImage or an extracted directory.
+pub fn get_install_size(install_dir: &Path, tag_name: &str) -> io::Result<u64> {
+    let extract_dir = install_dir.join(tag_name);
+    if extract_dir.is_dir() {
+        get_dir_size(&extract_dir)
+    } else {
+        Ok(fs::metadata(install_dir.join(format!("{}.AppImage", tag_name)))?.len())
+    }
+}
+
+fn get_dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.path().symlink_metadata()?;
+
+        size += if metadata.is_dir() {
+            get_dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+
+    Ok(size)
+}
+
+/// Returns the space available to unprivileged users on the filesystem of the path.
+///
+/// Walks up to the closest existing ancestor, as the path itself might not be created yet.
+#[cfg(target_family = "unix")]
+fn get_available_space(path: &Path) -> errors::Result<Option<u64>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = match path.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(existing) => existing,
+        None => return Ok(None),
+    };
+
+    let c_path = CString::new(existing.as_os_str().as_bytes()).expect("paths shouldn't contain null bytes");
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(Error::Io {
+            source: io::Error::last_os_error(),
+            context: Some(format!("Failed to check the free space of {}", existing.display())),
+        });
+    }
+
+    let stat = unsafe { stat.assume_init() };
+
+    // The field types differ between platforms, so the casts aren't always no-ops.
+    #[allow(clippy::unnecessary_cast)]
+    Ok(Some(stat.f_bavail as u64 * stat.f_frsize as u64))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn get_available_space(_path: &Path) -> errors::Result<Option<u64>> {
+    Ok(None)
+}
+
+/// Returns the directory osu! keeps its data in.
+///
+/// Follows the custom location set in `storage.ini`, if the user has moved their data.
+pub fn get_osu_data_dir() -> PathBuf {
+    let default_dir = dirs::data_dir()
+        .expect("Couldn't find your data directory.")
+        .join("osu");
+
+    let custom_dir = fs::read_to_string(default_dir.join("storage.ini"))
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "FullPath").then(|| PathBuf::from(value.trim()))
+            })
+        });
+
+    custom_dir.unwrap_or(default_dir)
+}
+
+/// Returns the directory an isolated version gets as `XDG_DATA_HOME`, so osu! keeps its data in `osu` inside it.
+pub fn get_isolated_data_home(install_dir: &Path, tag_name: &str) -> PathBuf {
+    install_dir.join("data").join(tag_name)
+}
+
+/// Returns the size of the isolated data of a version, or 0 if there is none.
+pub fn get_isolated_data_size(install_dir: &Path, tag_name: &str) -> io::Result<u64> {
+    let data_home = get_isolated_data_home(install_dir, tag_name);
+    if data_home.is_dir() {
+        get_dir_size(&data_home)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Copies the main osu! data into the isolated data directory of a version, replacing what's there.
+///
+/// `storage.ini` is left out, as it would point the copy right back at the main data.
+pub fn seed_isolated_data(install_dir: &Path, tag_name: &str) -> errors::Result<()> {
+    let source_dir = get_osu_data_dir();
+    if !source_dir.try_exists()? {
+        return Err(Error::Descriptive(format!(
+            "There is no osu! data to copy at {}.",
+            source_dir.display()
+        )));
+    }
+
+    let data_home = get_isolated_data_home(install_dir, tag_name);
+    let target_dir = data_home.join("osu");
+    ensure_free_space(install_dir, &[&data_home], get_dir_size(&source_dir)?)?;
+
+    print!("Copying the osu! data from {}...", source_dir.display());
+
+    remove_isolated_data(install_dir, tag_name)?;
+    copy_dir(&source_dir, &target_dir, &[OsStr::new("storage.ini")])?;
+
+    println!("\rSuccessfully copied the osu! data to {}!", target_dir.display());
+
+    Ok(())
+}
+
+/// Deletes the isolated data directory of a version, if it has one.
+pub fn remove_isolated_data(install_dir: &Path, tag_name: &str) -> errors::Result<()> {
+    let data_home = get_isolated_data_home(install_dir, tag_name);
+    match fs::remove_dir_all(&data_home) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::Io {
+            source: e,
+            context: Some(data_home.to_string_lossy().to_string()),
+        }),
+    }
+}
+
+/// Recursively copies a directory, keeping symlinks as they are and leaving out the excluded top level entries.
+fn copy_dir(source: &Path, target: &Path, excluded: &[&OsStr]) -> errors::Result<()> {
+    fs::create_dir_all(target).map_err(|e| Error::Io {
+        source: e,
+        context: Some(target.to_string_lossy().to_string()),
+    })?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if excluded.contains(&entry.file_name().as_os_str()) {
+            continue;
+        }
+
+        let target_path = target.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target_path, &[])?;
+        } else if file_type.is_symlink() {
+            symlink(&fs::read_link(entry.path())?, &target_path)?;
+        } else {
+            fs::copy(entry.path(), &target_path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(target_path.to_string_lossy().to_string()),
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the size of osu!'s `client.realm`, or 0 if there is none.
+pub fn get_realm_size() -> io::Result<u64> {
+    match fs::metadata(get_osu_data_dir().join("client.realm")) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Copies osu!'s `client.realm` into the install_dir before an update can migrate it forward.
+///
+/// Older snapshots are kept until the update is recorded, see [remove_stale_realm_snapshots].
+/// Returns [None] if there is no database to snapshot.
+pub fn snapshot_realm(install_dir: &Path, from_tag: &str) -> errors::Result<Option<PathBuf>> {
+    let realm_path = get_osu_data_dir().join("client.realm");
+    if !realm_path.try_exists()? {
+        return Ok(None);
+    }
+
+    let snapshots_dir = install_dir.join("snapshots");
+    let snapshot_path = snapshots_dir.join(format!("client.realm.{}", from_tag));
+    fs::create_dir_all(&snapshots_dir)?;
+
+    print!("Taking a snapshot of the osu! database...");
+    fs::copy(&realm_path, &snapshot_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(snapshot_path.to_string_lossy().to_string()),
+    })?;
+    println!("\rSaved a snapshot of the osu! database to {}", snapshot_path.display());
+
+    Ok(Some(snapshot_path))
+}
+
+/// Removes every realm snapshot but the one of the latest update, which is the only one a rollback can use.
+pub fn remove_stale_realm_snapshots(install_dir: &Path, kept: Option<&Path>) -> errors::Result<()> {
+    let snapshots_dir = install_dir.join("snapshots");
+    let entries = match fs::read_dir(&snapshots_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(Error::Io {
+                source: e,
+                context: Some(snapshots_dir.to_string_lossy().to_string()),
+            })
+        }
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        if Some(path.as_path()) != kept {
+            fs::remove_file(&path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(path.to_string_lossy().to_string()),
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Puts a realm snapshot back in place, keeping the current database next to it with a `.bak` suffix.
+pub fn restore_realm(snapshot_path: &Path) -> errors::Result<()> {
+    let realm_path = get_osu_data_dir().join("client.realm");
+    let backup_path = realm_path.with_extension("realm.bak");
+
+    if realm_path.try_exists()? {
+        fs::rename(&realm_path, &backup_path).map_err(|e| Error::Io {
+            source: e,
+            context: Some(backup_path.to_string_lossy().to_string()),
+        })?;
+        println!("Moved the current osu! database to {}", backup_path.display());
+    }
+
+    fs::copy(snapshot_path, &realm_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(realm_path.to_string_lossy().to_string()),
+    })?;
+    println!("Restored the osu! database from {}", snapshot_path.display());
+
+    Ok(())
+}
+
+/// Computes the hex encoded SHA-256 digest of a file.
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1024 * 1024];
+
+    loop {
+        let read_bytes = file.read(&mut buffer)?;
+        if read_bytes == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read_bytes]);
+    }
+
+    Ok(hex_digest(hasher))
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Looks up an executable by name in the directories of `$PATH`.
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Lists the desktop entries created by this tool, both the per-version ones and [ACTIVE_DESKTOP_ENTRY_NAME].
+pub fn get_desktop_entry_paths(local_data_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(local_data_dir.join("applications")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::with_capacity(0)),
+        Err(e) => return Err(e),
+    };
+
+    // Other entries starting with "osu!", like ones for a wine setup of osu!stable, belong to the user.
+    let mut paths = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let is_ours = name == ACTIVE_DESKTOP_ENTRY_NAME
+                || name.starts_with(PROFILE_ENTRY_PREFIX)
+                || name
+                    .strip_prefix("osu!-")
+                    .and_then(|name| name.strip_suffix(".desktop"))
+                    .is_some_and(appimage::is_release_tag);
+
+            (is_ours && name.ends_with(".desktop")).then_some(path)
+        })
+        .collect::<Vec<PathBuf>>();
+
+    paths.sort();
+    Ok(paths)
+}
+
+/// Why a desktop entry created by this tool can't launch anything anymore.
+pub enum OrphanReason {
+    MissingExec,
+    MissingProgram(String),
+    MissingVersion(String),
+    NoVersions,
+}
+
+impl std::fmt::Display for OrphanReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingExec => write!(f, "it doesn't have an Exec key"),
+            Self::MissingProgram(program) => write!(f, "it points to {}, which doesn't exist", program),
+            Self::MissingVersion(tag) => write!(f, "it launches {}, which isn't installed", tag),
+            Self::NoVersions => write!(f, "it launches the newest version, but none is installed"),
+        }
+    }
+}
+
+/// Checks whether a desktop entry created by this tool still launches something.
+///
+/// The program is either a path or a name looked up in `$PATH`. When it's this tool, the entry goes through the launch
+/// command and is only as good as the version it launches.
+pub fn get_orphan_reason(install_dir: &Path, entry_path: &Path) -> io::Result<Option<OrphanReason>> {
+    let args = match DesktopEntry::parse(&fs::read_to_string(entry_path)?).get_exec_args() {
+        Some(args) if !args.is_empty() => args,
+        _ => return Ok(Some(OrphanReason::MissingExec)),
+    };
+
+    let program = &args[0];
+    let program_path = match program.contains('/') {
+        true => Some(PathBuf::from(program)).filter(|path| path.exists()),
+        false => find_executable(program),
+    };
+    let program_path = match program_path {
+        Some(program_path) => program_path,
+        None => return Ok(Some(OrphanReason::MissingProgram(program.to_owned()))),
+    };
+
+    let current_exe = std::env::current_exe()?;
+    if program_path.file_name() != current_exe.file_name() {
+        return Ok(None);
+    }
+
+    let mut entry_install_dir = install_dir.to_path_buf();
+    let mut is_launch = false;
+    let mut target_tag = None;
+    let mut options = args[1..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--" => break,
+            "--install-dir" => entry_install_dir = options.next().map_or(entry_install_dir, PathBuf::from),
+            "--profile" => {
+                options.next();
+            }
+            "launch" if !is_launch => is_launch = true,
+            tag if is_launch && !tag.starts_with('-') => target_tag = Some(tag.to_owned()),
+            _ => {}
+        }
+    }
+
+    if !is_launch {
+        return Ok(None);
+    }
+
+    let installed_tags = get_local_release_tags(&entry_install_dir)?;
+    Ok(match target_tag {
+        Some(tag) if !installed_tags.contains(&tag) => Some(OrphanReason::MissingVersion(tag)),
+        None if installed_tags.is_empty() => Some(OrphanReason::NoVersions),
+        _ => None,
+    })
+}
+
 // TODO: Add support for other os alternatives.
-fn update_desktop_database(local_data_dir: &Path) -> errors::Result<()> {
+pub fn update_desktop_database(local_data_dir: &Path) -> errors::Result<()> {
     if cfg!(target_os = "linux") {
         print!("Updating the desktop database...");
 
@@ -118,40 +676,189 @@ fn set_permission_as_executable(file: &Path) -> errors::Result<()> {
     Ok(())
 }
 
-fn create_desktop_entry(name: &str, icon_dir: &Path, exec_path: &Path, entry_path: &Path) -> errors::Result<()> {
-    if cfg!(target_os = "linux") {
-        let desktop_entry_content = format!(
-            "[Desktop Entry]\n\
-        Name={name}\n\
-        Icon={icon_dir}\n\
-        Comment=rhythm is just a *click* away!\n\
-        Exec={exec_dir}\n\
-        Version=1.0\n\
-        Type=Application\n\
-        Categories=Game;",
-            icon_dir = icon_dir.canonicalize()?.to_str().unwrap(),
-            exec_dir = exec_path.canonicalize()?.to_str().unwrap(),
-        );
+/// Command line a desktop entry runs, before the `%U` field code.
+#[derive(Debug)]
+pub struct LaunchCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
 
-        print!("Creating the desktop entry...");
-        fs::write(entry_path, desktop_entry_content).map_err(|e| Error::Io {
-            source: e,
-            context: Some(entry_path.to_str().unwrap().to_owned()),
-        })?;
+impl LaunchCommand {
+    fn format_exec(&self) -> String {
+        let args = self.args.iter().map(String::as_str).collect::<Vec<&str>>();
+        desktop_entry::format_exec(&self.program, &args, &["%U"])
+    }
+}
 
-        println!("\rSuccessfully created the desktop entry at {}!", entry_path.display());
-    } else {
+/// Version a desktop entry launches.
+#[derive(Debug, Clone, Copy)]
+pub enum LaunchTarget<'a> {
+    Version(&'a str),
+    Active,
+    Newest,
+}
+
+/// Returns the command a desktop entry uses to start the target version, whose binary is at exec_path.
+///
+/// Without a launch profile, that's the binary itself. A profile can run commands around the game, the newest or active
+/// version may have to be updated first, and an isolated version needs its own data directory, so the entry goes
+/// through the launch command of this tool instead.
+fn get_launch_command(
+    install_dir: &Path,
+    config: &Config,
+    profile: Option<&str>,
+    target: LaunchTarget,
+    exec_path: PathBuf,
+) -> errors::Result<LaunchCommand> {
+    let profile = config.get_profile(profile)?.map(|(name, _)| name);
+    let checks_updates = config.launch.check_updates && !matches!(target, LaunchTarget::Version(_));
+    let target_tag = match target {
+        LaunchTarget::Version(tag) => Some(tag.to_owned()),
+        LaunchTarget::Active => get_active_tag(install_dir)?,
+        LaunchTarget::Newest => get_local_release_tags(install_dir)?.into_iter().next(),
+    };
+    let is_isolated =
+        target_tag.is_some_and(|tag| Manifest::load(install_dir).is_ok_and(|m| m.isolated.contains(&tag)));
+    if profile.is_none() && !checks_updates && !is_isolated {
+        return Ok(LaunchCommand {
+            program: exec_path,
+            args: Vec::new(),
+        });
+    }
+
+    let mut args = vec![
+        "--install-dir".to_owned(),
+        install_dir.canonicalize()?.to_string_lossy().to_string(),
+        "launch".to_owned(),
+    ];
+    if let Some(profile) = profile {
+        args.extend(["--profile".to_owned(), profile.to_owned()]);
+    }
+    match target {
+        LaunchTarget::Version(tag) => args.push(tag.to_owned()),
+        LaunchTarget::Active => args.push("--active".to_owned()),
+        LaunchTarget::Newest => {}
+    }
+    args.push("--".to_owned());
+
+    Ok(LaunchCommand {
+        program: std::env::current_exe()?.canonicalize()?,
+        args,
+    })
+}
+
+/// Window class osu! sets, so the running game is grouped with its desktop entry on taskbars.
+const STARTUP_WM_CLASS: &str = "osu!";
+
+/// Writes a desktop entry running the command, following the desktop template of the config.
+///
+/// Every action gets a `[Desktop Action]` group launching another version, given as its tag and command.
+/// An existing entry is rewritten in place: only the generated keys and actions are replaced, so its file ID and any
+/// other keys survive. The entry is validated before it's written.
+fn write_desktop_entry(
+    config: &DesktopConfig,
+    version: Option<&str>,
+    profile: Option<&str>,
+    command: &LaunchCommand,
+    entry_path: &Path,
+    actions: &[(String, LaunchCommand)],
+) -> errors::Result<()> {
+    if !cfg!(target_os = "linux") {
         println!("Desktop entry creation is only supported on Linux for now.");
+        return Ok(());
+    }
+
+    let existing = match fs::read_to_string(entry_path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(Error::Io {
+                source: e,
+                context: Some(entry_path.to_string_lossy().to_string()),
+            })
+        }
+    };
+
+    let format_name = |name_format: &str| match profile {
+        Some(profile) => format!("{} ({})", DesktopConfig::format_name(name_format, version), profile),
+        None => DesktopConfig::format_name(name_format, version),
+    };
+
+    let mut entry = existing.as_deref().map(DesktopEntry::parse).unwrap_or_default();
+    entry.set_raw("Type", "Application");
+    entry.set_raw("Version", "1.0");
+    entry.set_string("Name", &format_name(&config.name_format));
+    entry.remove_where(|key| key.starts_with("Name["));
+    for (locale, name_format) in &config.localized_names {
+        entry.set_string(&format!("Name[{}]", locale), &format_name(name_format));
+    }
+    entry.set_string("Comment", &config.comment);
+    entry.set_string("Icon", ICON_NAME);
+    entry.set_string("TryExec", &command.program.to_string_lossy());
+    entry.set_raw("Exec", command.format_exec());
+    entry.set_list("MimeType", &MIME_TYPES);
+    entry.set_list(
+        "Categories",
+        &config.categories.iter().map(String::as_str).collect::<Vec<&str>>(),
+    );
+    entry.set_string("StartupWMClass", STARTUP_WM_CLASS);
+    for (key, value) in &config.extra_keys {
+        entry.set_raw(key, value.as_str());
     }
 
+    entry.remove_groups_where(|group| group.starts_with("[Desktop Action "));
+    entry.remove_where(|key| key == "Actions");
+    if !actions.is_empty() {
+        let ids = actions
+            .iter()
+            .map(|(tag, _)| format!("osu-{}", tag.replace(|c: char| !c.is_ascii_alphanumeric(), "-")))
+            .collect::<Vec<String>>();
+        entry.set_list("Actions", &ids.iter().map(String::as_str).collect::<Vec<&str>>());
+
+        for (id, (tag, action_command)) in ids.iter().zip(actions) {
+            let group = format!("[Desktop Action {}]", id);
+            entry.set_raw_in(
+                &group,
+                "Name",
+                desktop_entry::escape(&DesktopConfig::format_name(&config.name_format, Some(tag))),
+            );
+            entry.set_raw_in(&group, "Exec", action_command.format_exec());
+        }
+    }
+
+    let problems = entry.validate();
+    if !problems.is_empty() {
+        return Err(Error::Descriptive(format!(
+            "The desktop entry at {} would be invalid:\n  - {}\nCheck the [desktop] section of the config.",
+            entry_path.display(),
+            problems.join("\n  - ")
+        )));
+    }
+
+    let action = if existing.is_some() { "updated" } else { "created" };
+    print!("Writing the desktop entry...");
+    fs::write(entry_path, entry.to_string()).map_err(|e| Error::Io {
+        source: e,
+        context: Some(entry_path.to_string_lossy().to_string()),
+    })?;
+    println!(
+        "\rSuccessfully {} the desktop entry at {}!",
+        action,
+        entry_path.display()
+    );
+
     Ok(())
 }
 
 /// Initializes all prerequisites required to move the [download_buffer] into a
 /// file and creates the desktop entry.
-pub fn initialize_binary(local_data_dir: &Path, install_dir: &Path, release: &GithubRelease) -> errors::Result<()> {
+pub fn initialize_binary(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    release: &GithubRelease,
+) -> errors::Result<()> {
     let install_data = InstallData::new(local_data_dir, install_dir, &release.tag_name);
-    let source_icon_path = install_dir.join("osu.png");
 
     if !install_dir.try_exists()? {
         fs::create_dir_all(install_dir).map_err(|e| Error::Io {
@@ -160,66 +867,685 @@ pub fn initialize_binary(local_data_dir: &Path, install_dir: &Path, release: &Gi
         })?;
     }
 
-    let desktop_entries_dir = install_data
-        .desktop_entry_path
-        .parent()
-        .expect("desktop_entry_path should be a path within a directory");
-    if !desktop_entries_dir.try_exists()? {
-        fs::create_dir_all(desktop_entries_dir).map_err(|e| Error::Io {
-            source: e,
-            context: Some(desktop_entries_dir.to_string_lossy().to_string()),
-        })?;
-    }
-
-    if install_data.install_path.try_exists()? {
-        // TODO check sizes are the same;
-        println!("Found a previous binary of this release, skipping download");
+    let extract = config.install.mode == InstallMode::Extracted;
+    let sha256 = if install_data.is_extracted() {
+        println!("Found a previous extracted copy of this release, skipping download");
+        None
     } else {
         let app_image_asset = release
             .get_app_image_asset()
             .expect("AppImage asset in missing from the release assets of this tag");
-        let download_buffer = net::download_release_asset(app_image_asset)?;
-        // let download_buffer = vec![0; 0];
-
-        fs::write(&install_data.install_path, download_buffer).map_err(|e| Error::Io {
-            source: e,
-            context: Some(install_data.install_path.to_str().unwrap().to_owned()),
-        })?;
-    }
 
-    #[cfg(target_family = "unix")]
-    set_permission_as_executable(&install_data.install_path)?;
+        // Older releases of this tool wrote the binary in place, so a previous one might have been cut short.
+        let is_previous_complete = install_data.install_path.try_exists()?
+            && match verify_app_image(&install_data.install_path, app_image_asset.size) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!(
+                        "Found a broken previous binary of this release, downloading it again: {}",
+                        e
+                    );
+                    false
+                }
+            };
 
-    if !source_icon_path.try_exists()? {
-        let icon_data = github::get_icon()?;
-        fs::write(&source_icon_path, icon_data)?;
-    }
+        let sha256 = if is_previous_complete {
+            println!("Found a previous binary of this release, skipping download");
+            file_sha256(&install_data.install_path)?
+        } else {
+            ensure_free_space(install_dir, &[install_dir], required_space(app_image_asset, extract))?;
+            download_binary(app_image_asset, &install_data.install_path)?
+        };
 
-    create_desktop_entry(
-        format!("osu! {version}", version = &release.tag_name).as_str(),
-        &source_icon_path,
-        &install_data.install_path,
-        &install_data.desktop_entry_path,
-    )?;
+        if extract {
+            extract_binary(&install_data)?;
+        }
 
-    update_desktop_database(local_data_dir)?;
+        Some(sha256)
+    };
 
-    Ok(())
+    finish_install(
+        local_data_dir,
+        install_dir,
+        config,
+        &release.tag_name,
+        release.channel(),
+        sha256,
+    )
 }
 
-/// Removes the binary and the desktop entry from their respective directories.
+/// Installs a local AppImage, detecting its version from its contents instead of trusting its file name.
 ///
-/// NOTE: This function internally handles all the errors and events, so
-/// there's no need to handle them externally.
-pub fn remove_binary(local_data_dir: &Path, install_dir: &Path, tag_name: &str) -> errors::Result<()> {
-    let install_data = InstallData::new(local_data_dir, install_dir, tag_name);
-
-    print!("Removing the {} binary...", tag_name);
-    ignore_io_not_found(
-        fs::remove_file(&install_data.install_path),
-        format!("Successfully removed the {} binary.", tag_name),
-        format!("Couldn't find the {} binary, skipping...", tag_name),
-    )?;
+/// Returns the detected version.
+pub fn install_from_file(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    source_path: &Path,
+) -> errors::Result<String> {
+    let source_size = fs::metadata(source_path)
+        .map_err(|e| Error::Io {
+            source: e,
+            context: Some(source_path.to_string_lossy().to_string()),
+        })?
+        .len();
+    verify_app_image(source_path, source_size)?;
+
+    let version_info = AppImage::open(source_path)?.identify()?;
+    let tag_name = match version_info.version() {
+        Some(version) => version.to_owned(),
+        None => {
+            return Err(Error::Descriptive(format!(
+                "Couldn't detect the version of {}.",
+                source_path.display()
+            )))
+        }
+    };
+    if !appimage::is_release_tag(&tag_name) {
+        return Err(Error::Descriptive(format!(
+            "{} reports an invalid version: {:?}",
+            source_path.display(),
+            tag_name
+        )));
+    }
+
+    if get_local_release_tags(install_dir)?.contains(&tag_name) {
+        return Err(Error::Descriptive(format!("{} is already installed.", tag_name)));
+    }
+
+    if !install_dir.try_exists()? {
+        fs::create_dir_all(install_dir).map_err(|e| Error::Io {
+            source: e,
+            context: Some(install_dir.to_string_lossy().to_string()),
+        })?;
+    }
+
+    let extract = config.install.mode == InstallMode::Extracted;
+    let required = if extract { source_size * 4 } else { source_size };
+    ensure_free_space(install_dir, &[install_dir], required)?;
+
+    let install_data = InstallData::new(local_data_dir, install_dir, &tag_name);
+    let temp_file = PendingFile::new(&install_data.install_path.with_extension("AppImage.part"));
+    let io_error = |e: io::Error| Error::Io {
+        source: e,
+        context: Some(temp_file.path().to_string_lossy().to_string()),
+    };
+
+    print!("Copying {}...", source_path.display());
+    fs::copy(source_path, temp_file.path()).map_err(io_error)?;
+    fs::File::open(temp_file.path())
+        .and_then(|file| file.sync_all())
+        .map_err(io_error)?;
+    let sha256 = file_sha256(temp_file.path()).map_err(io_error)?;
+    fs::rename(temp_file.path(), &install_data.install_path).map_err(io_error)?;
+    temp_file.commit();
+    println!("\rSuccessfully copied {}.", source_path.display());
+
+    if extract {
+        extract_binary(&install_data)?;
+    }
+
+    finish_install(
+        local_data_dir,
+        install_dir,
+        config,
+        &tag_name,
+        Channel::from_tag(&tag_name),
+        Some(sha256),
+    )?;
+
+    Ok(tag_name)
+}
+
+/// Integrates a binary that is in place, records it in the manifest, and marks it as active if needed.
+fn finish_install(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    tag_name: &str,
+    channel: Channel,
+    sha256: Option<String>,
+) -> errors::Result<()> {
+    let install_data = InstallData::new(local_data_dir, install_dir, tag_name);
+
+    // A desktop entry created here is rolled back if anything after it fails or gets interrupted.
+    let created_entry =
+        if config.desktop.writes_per_version_entries() && !install_data.desktop_entry_path.try_exists()? {
+            Some(PendingFile::new(&install_data.desktop_entry_path))
+        } else {
+            None
+        };
+
+    // So are the manifest and the active version, which would otherwise point at a version that isn't fully installed.
+    let mut restored_paths = vec![
+        Manifest::path(install_dir),
+        install_dir.join(ACTIVE_LINK_NAME),
+        local_data_dir.join("applications").join(ACTIVE_DESKTOP_ENTRY_NAME),
+    ];
+    if let Some(desktop_dir) = dirs::desktop_dir() {
+        restored_paths.push(desktop_dir.join(ACTIVE_DESKTOP_ENTRY_NAME));
+    }
+    let restores = restored_paths
+        .iter()
+        .map(|path| {
+            PendingRestore::new(path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(path.to_string_lossy().to_string()),
+            })
+        })
+        .collect::<errors::Result<Vec<_>>>()?;
+
+    integrate_binary(local_data_dir, install_dir, config, tag_name)?;
+
+    let mut manifest = Manifest::load(install_dir)?;
+    let sha256 = sha256.or_else(|| manifest.versions.get(tag_name).and_then(|entry| entry.sha256.clone()));
+    manifest
+        .versions
+        .insert(tag_name.to_owned(), VersionEntry::new(channel, sha256));
+    manifest.save(install_dir)?;
+
+    let is_newest = get_local_release_tags(install_dir)?.first().map(String::as_str) == Some(tag_name);
+    if config.desktop.follows_newest() && (is_newest || get_active_tag(install_dir)?.is_none()) {
+        set_active_binary(local_data_dir, install_dir, config, tag_name)?;
+    } else {
+        refresh_version_actions(local_data_dir, install_dir, config)?;
+    }
+
+    update_desktop_database(local_data_dir)?;
+
+    if let Some(created_entry) = created_entry {
+        created_entry.commit();
+    }
+    restores.into_iter().for_each(PendingRestore::commit);
+
+    Ok(())
+}
+
+/// Downloads a fresh copy of an installed binary and swaps it in place of the current one.
+///
+/// An extracted version is extracted again. The desktop entry and the manifest entry of the version are kept, apart
+/// from the recorded checksum.
+pub fn reinstall_binary(local_data_dir: &Path, install_dir: &Path, release: &GithubRelease) -> errors::Result<()> {
+    let install_data = InstallData::new(local_data_dir, install_dir, &release.tag_name);
+    let app_image_asset = match release.get_app_image_asset() {
+        Some(asset) => asset,
+        None => {
+            return Err(Error::Descriptive(format!(
+                "The {} release doesn't have an AppImage asset.",
+                release.tag_name
+            )))
+        }
+    };
+
+    let extract = install_data.is_extracted();
+    ensure_free_space(install_dir, &[install_dir], required_space(app_image_asset, extract))?;
+    let sha256 = download_binary(app_image_asset, &install_data.install_path)?;
+
+    if extract {
+        extract_binary(&install_data)?;
+    } else {
+        #[cfg(target_family = "unix")]
+        set_permission_as_executable(&install_data.install_path)?;
+    }
+
+    let mut manifest = Manifest::load(install_dir)?;
+    manifest
+        .versions
+        .entry(release.tag_name.clone())
+        .and_modify(|entry| entry.sha256 = Some(sha256.clone()))
+        .or_insert_with(|| VersionEntry::new(release.channel(), Some(sha256)));
+    manifest.save(install_dir)
+}
+
+/// Sets up everything around an already downloaded binary: its permissions, the icon and its desktop entry.
+///
+/// This is the part of [initialize_binary] that doesn't need a download, so it can be re-run to repair an install.
+/// NOTE: Callers are responsible for updating the desktop database afterwards.
+pub fn integrate_binary(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    tag_name: &str,
+) -> errors::Result<()> {
+    let install_data = InstallData::new(local_data_dir, install_dir, tag_name);
+
+    let desktop_entries_dir = install_data
+        .desktop_entry_path
+        .parent()
+        .expect("desktop_entry_path should be a path within a directory");
+    if !desktop_entries_dir.try_exists()? {
+        fs::create_dir_all(desktop_entries_dir).map_err(|e| Error::Io {
+            source: e,
+            context: Some(desktop_entries_dir.to_string_lossy().to_string()),
+        })?;
+    }
+
+    // Extracted files keep the permissions they had in the AppImage.
+    #[cfg(target_family = "unix")]
+    if !install_data.is_extracted() {
+        set_permission_as_executable(&install_data.install_path)?;
+    }
+
+    install_icons(local_data_dir, &install_data)?;
+    install_mime_types(local_data_dir)?;
+
+    if config.desktop.writes_per_version_entries() {
+        let command = get_launch_command(
+            install_dir,
+            config,
+            None,
+            LaunchTarget::Version(tag_name),
+            install_data.canonical_exec_path()?,
+        )?;
+        write_desktop_entry(
+            &config.desktop,
+            Some(tag_name),
+            None,
+            &command,
+            &install_data.desktop_entry_path,
+            &[],
+        )?;
+    } else if config.desktop.actions && install_data.desktop_entry_path.try_exists()? {
+        // The version is an action of the "osu!" entry instead.
+        fs::remove_file(&install_data.desktop_entry_path).map_err(|e| Error::Io {
+            source: e,
+            context: Some(install_data.desktop_entry_path.to_string_lossy().to_string()),
+        })?;
+    }
+
+    write_profile_desktop_entries(local_data_dir, install_dir, config)?;
+    remove_legacy_icon(local_data_dir, install_dir)
+}
+
+/// Removes the copy of the icon entries used to reference by path, before it was installed into the icon theme.
+///
+/// It's kept as long as an entry that wasn't rewritten since still references it, e.g. of another installed version.
+fn remove_legacy_icon(local_data_dir: &Path, install_dir: &Path) -> errors::Result<()> {
+    let legacy_icon_path = install_dir.join("osu.png");
+    let canonical_icon_path = match legacy_icon_path.canonicalize() {
+        Ok(path) => path,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(Error::from(e)),
+    };
+
+    for entry_path in get_desktop_entry_paths(local_data_dir)? {
+        let entry = DesktopEntry::parse(&fs::read_to_string(&entry_path)?);
+        let icon_path = entry
+            .get_string("Icon")
+            .and_then(|icon| Path::new(&icon).canonicalize().ok());
+        if icon_path.as_ref() == Some(&canonical_icon_path) {
+            return Ok(());
+        }
+    }
+
+    fs::remove_file(&legacy_icon_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(legacy_icon_path.to_string_lossy().to_string()),
+    })
+}
+
+/// Prefix of the desktop entries of launch profiles, followed by the name of the profile.
+const PROFILE_ENTRY_PREFIX: &str = "osu!-profile-";
+
+/// Writes an entry for every launch profile that asks for one, and removes the entries of the other profiles.
+fn write_profile_desktop_entries(local_data_dir: &Path, install_dir: &Path, config: &Config) -> errors::Result<()> {
+    let applications_dir = local_data_dir.join("applications");
+    let newest_tag = get_local_release_tags(install_dir)?.into_iter().next();
+
+    for (name, profile) in &config.profiles {
+        let (Some(newest_tag), true) = (&newest_tag, profile.desktop_entry) else {
+            continue;
+        };
+
+        let exec_path = InstallData::new(local_data_dir, install_dir, newest_tag).canonical_exec_path()?;
+        let command = get_launch_command(install_dir, config, Some(name), LaunchTarget::Newest, exec_path)?;
+        let entry_path = applications_dir.join(format!("{}{}.desktop", PROFILE_ENTRY_PREFIX, name));
+        write_desktop_entry(&config.desktop, None, Some(name), &command, &entry_path, &[])?;
+    }
+
+    let keep_profile = |name: &str| newest_tag.is_some() && config.profiles.get(name).is_some_and(|p| p.desktop_entry);
+    for entry_path in get_desktop_entry_paths(local_data_dir)? {
+        let file_name = entry_path.file_name().unwrap_or_default().to_string_lossy();
+        let profile = match file_name
+            .strip_prefix(PROFILE_ENTRY_PREFIX)
+            .and_then(|name| name.strip_suffix(".desktop"))
+        {
+            Some(profile) => profile,
+            None => continue,
+        };
+
+        if !keep_profile(profile) {
+            fs::remove_file(&entry_path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(entry_path.to_string_lossy().to_string()),
+            })?;
+            println!("Removed the desktop entry of the {} launch profile.", profile);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the hicolor icon theme directory of the user.
+fn get_icon_theme_dir(local_data_dir: &Path) -> PathBuf {
+    local_data_dir.join("icons").join("hicolor")
+}
+
+/// Returns the paths of every size of [ICON_NAME] installed into the icon theme.
+pub fn get_installed_icon_paths(local_data_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let size_dirs = match fs::read_dir(get_icon_theme_dir(local_data_dir)) {
+        Ok(size_dirs) => size_dirs,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut paths = Vec::new();
+    for size_dir in size_dirs {
+        let apps_dir = size_dir?.path().join("apps");
+        for extension in ["png", "svg"] {
+            let path = apps_dir.join(format!("{}.{}", ICON_NAME, extension));
+            if path.try_exists()? {
+                paths.push(path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Installs the icons bundled in the binary into the icon theme, at the sizes they were bundled in.
+///
+/// Icons are read from the binary itself, so no network access is needed.
+fn install_icons(local_data_dir: &Path, install_data: &InstallData) -> errors::Result<()> {
+    let icons = if install_data.is_extracted() {
+        appimage::extracted_icons(&install_data.extract_dir)?
+    } else {
+        AppImage::open(&install_data.install_path)?.icons()?
+    };
+
+    if icons.is_empty() {
+        println!("Couldn't find an icon in the binary, desktop entries will show a generic icon.");
+        return Ok(());
+    }
+
+    print!("Installing the icon...");
+    for icon in &icons {
+        let apps_dir = get_icon_theme_dir(local_data_dir).join(&icon.size).join("apps");
+        let icon_path = apps_dir.join(format!("{}.{}", ICON_NAME, icon.extension));
+
+        fs::create_dir_all(&apps_dir)
+            .and_then(|_| fs::write(&icon_path, &icon.data))
+            .map_err(|e| Error::Io {
+                source: e,
+                context: Some(icon_path.to_string_lossy().to_string()),
+            })?;
+    }
+    println!("\rSuccessfully installed the icon in {} size(s).", icons.len());
+
+    Ok(())
+}
+
+fn remove_icons(local_data_dir: &Path) -> errors::Result<()> {
+    for icon_path in get_installed_icon_paths(local_data_dir)? {
+        fs::remove_file(&icon_path).map_err(|e| Error::Io {
+            source: e,
+            context: Some(icon_path.to_string_lossy().to_string()),
+        })?;
+    }
+
+    Ok(())
+}
+
+fn get_mime_package_path(local_data_dir: &Path) -> PathBuf {
+    local_data_dir.join("mime").join("packages").join("osu!.xml")
+}
+
+/// Installs the definitions of the osu! file formats into the user's MIME database, if they aren't up to date.
+fn install_mime_types(local_data_dir: &Path) -> errors::Result<()> {
+    let package_path = get_mime_package_path(local_data_dir);
+    if fs::read_to_string(&package_path).is_ok_and(|content| content == MIME_PACKAGE) {
+        return Ok(());
+    }
+
+    print!("Registering the osu! file types...");
+    let packages_dir = package_path
+        .parent()
+        .expect("package_path should be a path within a directory");
+    fs::create_dir_all(packages_dir)
+        .and_then(|_| fs::write(&package_path, MIME_PACKAGE))
+        .map_err(|e| Error::Io {
+            source: e,
+            context: Some(package_path.to_string_lossy().to_string()),
+        })?;
+    println!("\rSuccessfully registered the osu! file types.");
+
+    update_mime_database(local_data_dir)
+}
+
+fn remove_mime_types(local_data_dir: &Path) -> errors::Result<()> {
+    let package_path = get_mime_package_path(local_data_dir);
+    if !package_path.try_exists()? {
+        return Ok(());
+    }
+
+    fs::remove_file(&package_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(package_path.to_string_lossy().to_string()),
+    })?;
+
+    update_mime_database(local_data_dir)
+}
+
+fn update_mime_database(local_data_dir: &Path) -> errors::Result<()> {
+    if find_executable("update-mime-database").is_none() {
+        println!("Couldn't find update-mime-database, osu! files may not be recognized until it runs.");
+        return Ok(());
+    }
+
+    print!("Updating the MIME database...");
+    let output = std::process::Command::new("update-mime-database")
+        .arg(local_data_dir.join("mime"))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::Descriptive(format!(
+            "Failed to update the MIME database:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    println!("\rSuccessfully updated the MIME database!");
+    Ok(())
+}
+
+/// Makes the desktop entry the default application for [MIME_TYPES], so osu! files and links open with it.
+fn set_default_handler(desktop_entry_name: &str) -> errors::Result<()> {
+    if find_executable("xdg-mime").is_none() {
+        println!("Couldn't find xdg-mime, skipping the file associations...");
+        return Ok(());
+    }
+
+    print!("Associating osu! files and links with {}...", desktop_entry_name);
+    let output = std::process::Command::new("xdg-mime")
+        .arg("default")
+        .arg(desktop_entry_name)
+        .args(MIME_TYPES)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::Descriptive(format!(
+            "Failed to associate osu! files and links:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    println!(
+        "\rSuccessfully associated osu! files and links with {}.",
+        desktop_entry_name
+    );
+    Ok(())
+}
+
+/// Removes desktop entries created by this tool that can't launch anything anymore, see [get_orphan_reason].
+///
+/// Returns the paths of the removed entries, with the reason they were removed.
+pub fn remove_orphaned_desktop_entries(
+    local_data_dir: &Path,
+    install_dir: &Path,
+) -> errors::Result<Vec<(PathBuf, OrphanReason)>> {
+    let mut removed = Vec::new();
+
+    for entry_path in get_desktop_entry_paths(local_data_dir)? {
+        if let Some(reason) = get_orphan_reason(install_dir, &entry_path)? {
+            fs::remove_file(&entry_path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(entry_path.to_string_lossy().to_string()),
+            })?;
+            removed.push((entry_path, reason));
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Checks that a downloaded file has the expected size, and starts with the ELF and type 2 AppImage magic bytes.
+fn verify_app_image(path: &Path, expected_size: u64) -> errors::Result<()> {
+    let io_error = |e: io::Error| Error::Io {
+        source: e,
+        context: Some(path.to_string_lossy().to_string()),
+    };
+
+    let size = fs::metadata(path).map_err(io_error)?.len();
+    if size != expected_size {
+        return Err(Error::Descriptive(format!(
+            "The downloaded file is incomplete. ({} != {})",
+            size, expected_size
+        )));
+    }
+
+    let mut header = [0; 11];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(io_error)?;
+
+    if &header[..4] != b"\x7fELF" || &header[8..11] != b"AI\x02" {
+        return Err(Error::Descriptive(
+            "The downloaded file isn't a valid AppImage.".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Downloads the asset into a temporary file next to the install_path, and moves it into place once it's complete.
+///
+/// The temporary file is synced to disk before the rename, so the install_path only ever holds a complete binary.
+/// Returns the hex encoded SHA-256 of the binary.
+fn download_binary(asset: &GithubReleaseAsset, install_path: &Path) -> errors::Result<String> {
+    let temp_file = PendingFile::new(&install_path.with_extension("AppImage.part"));
+    let io_error = |e: io::Error| Error::Io {
+        source: e,
+        context: Some(temp_file.path().to_string_lossy().to_string()),
+    };
+
+    let mut file = fs::File::create(temp_file.path()).map_err(io_error)?;
+    net::download_release_asset(asset, &mut file)?;
+    file.sync_all().map_err(io_error)?;
+    drop(file);
+
+    verify_app_image(temp_file.path(), asset.size)?;
+    let sha256 = file_sha256(temp_file.path()).map_err(io_error)?;
+
+    fs::rename(temp_file.path(), install_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(install_path.to_string_lossy().to_string()),
+    })?;
+    temp_file.commit();
+
+    // Make sure the rename itself survives a crash.
+    #[cfg(target_family = "unix")]
+    if let Some(parent) = install_path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(sha256)
+}
+
+/// Space needed to install the asset. Extracting needs room for the AppImage and its unpacked payload at once.
+fn required_space(asset: &GithubReleaseAsset, extract: bool) -> u64 {
+    if extract {
+        asset.size * 4
+    } else {
+        asset.size
+    }
+}
+
+/// Unpacks a downloaded AppImage into the extract_dir of the version, and removes the AppImage afterwards.
+///
+/// The payload is unpacked into a temporary directory first, so the extract_dir only ever holds a complete copy.
+fn extract_binary(install_data: &InstallData) -> errors::Result<()> {
+    let temp_dir = PendingFile::new(&path_with_suffix(&install_data.extract_dir, ".part"));
+    let old_dir = path_with_suffix(&install_data.extract_dir, ".old");
+    let io_error = |path: &Path| {
+        let context = path.to_string_lossy().to_string();
+        move |e: io::Error| Error::Io {
+            source: e,
+            context: Some(context),
+        }
+    };
+
+    print!("Extracting the AppImage...");
+    for stale_dir in [temp_dir.path(), old_dir.as_path()] {
+        if stale_dir.try_exists()? {
+            fs::remove_dir_all(stale_dir).map_err(io_error(stale_dir))?;
+        }
+    }
+
+    AppImage::open(&install_data.install_path)?.extract_to(temp_dir.path())?;
+
+    if install_data.extract_dir.try_exists()? {
+        fs::rename(&install_data.extract_dir, &old_dir).map_err(io_error(&install_data.extract_dir))?;
+    }
+    fs::rename(temp_dir.path(), &install_data.extract_dir).map_err(io_error(&install_data.extract_dir))?;
+    temp_dir.commit();
+
+    if old_dir.try_exists()? {
+        fs::remove_dir_all(&old_dir).map_err(io_error(&old_dir))?;
+    }
+    fs::remove_file(&install_data.install_path).map_err(io_error(&install_data.install_path))?;
+    println!("\rSuccessfully extracted the AppImage.");
+
+    Ok(())
+}
+
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Removes the binary and the desktop entry from their respective directories.
+///
+/// NOTE: This function internally handles all the errors and events, so
+/// there's no need to handle them externally.
+pub fn remove_binary(local_data_dir: &Path, install_dir: &Path, config: &Config, tag_name: &str) -> errors::Result<()> {
+    let install_data = InstallData::new(local_data_dir, install_dir, tag_name);
+
+    if install_data.is_extracted() {
+        print!("Removing the {} extracted files...", tag_name);
+        ignore_io_not_found(
+            fs::remove_dir_all(&install_data.extract_dir),
+            format!("Successfully removed the {} extracted files.", tag_name),
+            format!("Couldn't find the {} extracted files, skipping...", tag_name),
+        )?;
+    } else {
+        print!("Removing the {} binary...", tag_name);
+        ignore_io_not_found(
+            fs::remove_file(&install_data.install_path),
+            format!("Successfully removed the {} binary.", tag_name),
+            format!("Couldn't find the {} binary, skipping...", tag_name),
+        )?;
+    }
 
     print!("Removing the {} desktop entry...", tag_name);
     ignore_io_not_found(
@@ -228,20 +1554,229 @@ pub fn remove_binary(local_data_dir: &Path, install_dir: &Path, tag_name: &str)
         format!("Couldn't find the {} desktop entry, skipping...", tag_name),
     )?;
 
+    let mut manifest = Manifest::load(install_dir)?;
+    let was_recorded = manifest.versions.remove(tag_name).is_some();
+    let was_pinned = manifest.pinned.remove(tag_name);
+    let was_isolated = manifest.isolated.remove(tag_name);
+    if was_recorded || was_pinned || was_isolated {
+        manifest.save(install_dir)?;
+    }
+
+    // The osu! data of a version is worth more than its binary, so it's never removed along with it.
+    let data_home = get_isolated_data_home(install_dir, tag_name);
+    if data_home.try_exists()? {
+        println!(
+            "The isolated osu! data of {} is kept in {}, delete it yourself if you don't need it anymore.",
+            tag_name,
+            data_home.display()
+        );
+    }
+
+    if get_active_tag(install_dir)?.as_deref() == Some(tag_name) {
+        let remaining_tags = get_local_release_tags(install_dir)?;
+        match remaining_tags.first() {
+            Some(tag) => set_active_binary(local_data_dir, install_dir, config, tag)?,
+            None => clear_active_binary(local_data_dir, install_dir)?,
+        }
+    } else {
+        refresh_version_actions(local_data_dir, install_dir, config)?;
+    }
+
+    if get_local_release_tags(install_dir)?.is_empty() {
+        remove_icons(local_data_dir)?;
+        remove_mime_types(local_data_dir)?;
+        write_profile_desktop_entries(local_data_dir, install_dir, config)?;
+    }
+
     update_desktop_database(local_data_dir)?;
 
     Ok(())
 }
 
+/// Returns the tag of the binary [ACTIVE_LINK_NAME] points to, if there's one.
+pub fn get_active_tag(install_dir: &Path) -> io::Result<Option<String>> {
+    match fs::read_link(install_dir.join(ACTIVE_LINK_NAME)) {
+        // The link points to either `<tag>.AppImage` or `<tag>/AppRun`.
+        Ok(target) => Ok(target
+            .components()
+            .next()
+            .map(|name| name.as_os_str().to_string_lossy().replace(".AppImage", ""))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound || e.kind() == io::ErrorKind::InvalidInput => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Points [ACTIVE_LINK_NAME] to the binary of the given tag, and creates the "osu!" desktop entry launching it.
+///
+/// The "osu!" desktop entry keeps its file ID, and an existing one is rewritten in place, so dock pins survive.
+/// It's also made the default handler of osu! files and links, so they open with the active version.
+///
+/// The link is swapped atomically, so a running game or desktop entry never sees a missing launcher.
+///
+/// NOTE: Callers are responsible for updating the desktop database afterwards.
+pub fn set_active_binary(
+    local_data_dir: &Path,
+    install_dir: &Path,
+    config: &Config,
+    tag_name: &str,
+) -> errors::Result<()> {
+    let install_data = InstallData::new(local_data_dir, install_dir, tag_name);
+    let exec_path = install_data.exec_path();
+    if !exec_path.try_exists()? {
+        return Err(Error::Descriptive(format!(
+            "Couldn't find the {} binary to mark as active.",
+            tag_name
+        )));
+    }
+
+    let link_path = install_dir.join(ACTIVE_LINK_NAME);
+    let temp_link_path = install_dir.join(format!("{}.tmp", ACTIVE_LINK_NAME));
+    let target = exec_path
+        .strip_prefix(install_dir)
+        .expect("exec_path should always be within the install_dir");
+
+    print!("Marking {} as the active version...", tag_name);
+    if let Err(e) = fs::remove_file(&temp_link_path) {
+        if e.kind() != io::ErrorKind::NotFound {
+            return Err(Error::from(e));
+        }
+    }
+
+    symlink(target, &temp_link_path)?;
+    fs::rename(&temp_link_path, &link_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(link_path.to_string_lossy().to_string()),
+    })?;
+    println!("\rSuccessfully marked {} as the active version.", tag_name);
+
+    write_active_desktop_entry(local_data_dir, install_dir, config)?;
+    set_default_handler(ACTIVE_DESKTOP_ENTRY_NAME)
+}
+
+/// Writes the "osu!" desktop entry launching [ACTIVE_LINK_NAME], with an action for every other installed version
+/// in the actions mode.
+fn write_active_desktop_entry(local_data_dir: &Path, install_dir: &Path, config: &Config) -> errors::Result<()> {
+    let mut actions = Vec::new();
+    if config.desktop.actions {
+        let active_tag = get_active_tag(install_dir)?;
+        for tag in get_local_release_tags(install_dir)? {
+            if active_tag.as_ref() != Some(&tag) {
+                let exec_path = InstallData::new(local_data_dir, install_dir, &tag).canonical_exec_path()?;
+                let command = get_launch_command(install_dir, config, None, LaunchTarget::Version(&tag), exec_path)?;
+                actions.push((tag, command));
+            }
+        }
+    }
+
+    let entry_path = local_data_dir.join("applications").join(ACTIVE_DESKTOP_ENTRY_NAME);
+    // The link is used as is, so the entry follows it instead of the version it currently points to.
+    let command = get_launch_command(
+        install_dir,
+        config,
+        None,
+        LaunchTarget::Active,
+        install_dir.canonicalize()?.join(ACTIVE_LINK_NAME),
+    )?;
+    write_desktop_entry(&config.desktop, None, None, &command, &entry_path, &actions)?;
+
+    if config.desktop.desktop_shortcut {
+        create_desktop_shortcut(&entry_path)?;
+    }
+
+    Ok(())
+}
+
+/// Regenerates the actions of the "osu!" desktop entry after the set of installed versions changed.
+fn refresh_version_actions(local_data_dir: &Path, install_dir: &Path, config: &Config) -> errors::Result<()> {
+    if !config.desktop.actions || get_active_tag(install_dir)?.is_none() {
+        return Ok(());
+    }
+
+    write_active_desktop_entry(local_data_dir, install_dir, config)
+}
+
+/// Copies the desktop entry into the desktop directory, where it needs to be executable to be trusted.
+fn create_desktop_shortcut(entry_path: &Path) -> errors::Result<()> {
+    let shortcut_path = match dirs::desktop_dir() {
+        Some(desktop_dir) if desktop_dir.is_dir() => desktop_dir.join(ACTIVE_DESKTOP_ENTRY_NAME),
+        _ => {
+            println!("Couldn't find your desktop directory, skipping the desktop shortcut...");
+            return Ok(());
+        }
+    };
+
+    print!("Creating the desktop shortcut...");
+    fs::copy(entry_path, &shortcut_path).map_err(|e| Error::Io {
+        source: e,
+        context: Some(shortcut_path.to_string_lossy().to_string()),
+    })?;
+
+    #[cfg(target_family = "unix")]
+    set_permission_as_executable(&shortcut_path)?;
+    println!(
+        "\rSuccessfully created the desktop shortcut at {}!",
+        shortcut_path.display()
+    );
+
+    Ok(())
+}
+
+/// Removes [ACTIVE_LINK_NAME] and the "osu!" desktop entry.
+pub fn clear_active_binary(local_data_dir: &Path, install_dir: &Path) -> errors::Result<()> {
+    print!("Removing the active version link...");
+    ignore_io_not_found(
+        fs::remove_file(install_dir.join(ACTIVE_LINK_NAME)),
+        "Successfully removed the active version link.".to_owned(),
+        "Couldn't find the active version link, skipping...".to_owned(),
+    )?;
+
+    print!("Removing the osu! desktop entry...");
+    ignore_io_not_found(
+        fs::remove_file(local_data_dir.join("applications").join(ACTIVE_DESKTOP_ENTRY_NAME)),
+        "Successfully removed the osu! desktop entry.".to_owned(),
+        "Couldn't find the osu! desktop entry, skipping...".to_owned(),
+    )?;
+
+    if let Some(desktop_dir) = dirs::desktop_dir() {
+        let shortcut_path = desktop_dir.join(ACTIVE_DESKTOP_ENTRY_NAME);
+        if shortcut_path.try_exists()? {
+            fs::remove_file(&shortcut_path).map_err(|e| Error::Io {
+                source: e,
+                context: Some(shortcut_path.to_string_lossy().to_string()),
+            })?;
+            println!("Successfully removed the desktop shortcut.");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+fn symlink(original: &Path, link: &Path) -> errors::Result<()> {
+    std::os::unix::fs::symlink(original, link).map_err(|e| Error::Io {
+        source: e,
+        context: Some(link.to_string_lossy().to_string()),
+    })
+}
+
+#[cfg(not(target_family = "unix"))]
+fn symlink(_original: &Path, _link: &Path) -> errors::Result<()> {
+    Err(Error::Descriptive(
+        "Marking a version as active is only supported on unix based systems for now.".to_owned(),
+    ))
+}
+
 #[derive(Debug)]
 /// Contains common paths and file names required to manipulate a single binary.
-struct InstallData {
+pub struct InstallData {
     pub desktop_entry_path: PathBuf,
     pub install_path: PathBuf,
+    /// Directory holding the unpacked payload, when the version is installed in the extracted mode.
+    pub extract_dir: PathBuf,
 }
 
 impl InstallData {
-    fn new(local_data_dir: &Path, install_dir: &Path, release_tag_name: &str) -> Self {
+    pub fn new(local_data_dir: &Path, install_dir: &Path, release_tag_name: &str) -> Self {
         let desktop_dir = local_data_dir.join("applications");
         let app_image_file_name = format!("{}.AppImage", release_tag_name);
         let desktop_file_name = format!("osu!-{}.desktop", release_tag_name);
@@ -249,8 +1784,34 @@ impl InstallData {
         Self {
             install_path: install_dir.join(app_image_file_name),
             desktop_entry_path: desktop_dir.join(desktop_file_name),
+            extract_dir: install_dir.join(release_tag_name),
+        }
+    }
+
+    pub fn is_extracted(&self) -> bool {
+        self.extract_dir.join("AppRun").symlink_metadata().is_ok()
+    }
+
+    /// Returns the file that launches the version: the AppImage itself, or the `AppRun` of an extracted copy.
+    pub fn exec_path(&self) -> PathBuf {
+        if self.is_extracted() {
+            self.extract_dir.join("AppRun")
+        } else {
+            self.install_path.clone()
         }
     }
+
+    /// Returns [InstallData::exec_path] as an absolute path, without following an `AppRun` symlink out of its
+    /// directory, as `AppRun` relies on being launched from there.
+    pub fn canonical_exec_path(&self) -> io::Result<PathBuf> {
+        let exec_path = self.exec_path();
+        let parent = exec_path
+            .parent()
+            .expect("exec_path should be a path within a directory");
+        let file_name = exec_path.file_name().expect("exec_path should always have a file name");
+
+        Ok(parent.canonicalize()?.join(file_name))
+    }
 }
 
 #[cfg(test)]
@@ -294,6 +1855,30 @@ mod test {
         )
     }
 
+    #[test]
+    fn prunable_tags_respect_channels_and_protection() {
+        use crate::github::Channel;
+        use std::collections::HashMap;
+
+        let tags = vec![
+            String::from("2023.720.0"),
+            String::from("2023.718.0"),
+            String::from("2023.717.0"),
+            String::from("2023.617.0"),
+            String::from("2023.612.0"),
+        ];
+        let channels = HashMap::from([
+            ("2023.720.0", Channel::Tachyon),
+            ("2023.718.0", Channel::Tachyon),
+            ("2023.717.0", Channel::Lazer),
+            ("2023.617.0", Channel::Lazer),
+            ("2023.612.0", Channel::Lazer),
+        ]);
+
+        let prunable = super::select_prunable_tags(&tags, &channels, &["2023.612.0"], 1);
+        assert_eq!(prunable, vec![String::from("2023.718.0"), String::from("2023.617.0")]);
+    }
+
     #[test]
     fn test_install_data() {
         let local_data_dir = Path::new("/home/username/.local/share");
@@ -310,5 +1895,50 @@ mod test {
             install_data.desktop_entry_path,
             Path::new("/home/username/.local/share/applications/osu!-2023.617.0.desktop")
         );
+        assert_eq!(
+            install_data.extract_dir,
+            Path::new("/home/username/.local/share/games/osu!/2023.617.0")
+        );
+    }
+
+    #[test]
+    fn only_entries_of_this_tool_are_orphaned() {
+        use std::fs;
+
+        let root = std::env::temp_dir().join(format!("osu-helper-entries-{}", std::process::id()));
+        let applications_dir = root.join("data").join("applications");
+        let install_dir = root.join("install");
+        fs::create_dir_all(&applications_dir).unwrap();
+        fs::create_dir_all(&install_dir).unwrap();
+
+        let write_entry = |name: &str, exec: &str| {
+            let path = applications_dir.join(name);
+            fs::write(&path, format!("[Desktop Entry]\nType=Application\nExec={}\n", exec)).unwrap();
+            path
+        };
+        write_entry("osu!-stable.desktop", "osu-wine %U");
+        let missing_binary = write_entry("osu!-2023.617.0.desktop", "/nonexistent/2023.617.0.AppImage %U");
+        let bare_name = write_entry("osu!.desktop", "sh %U");
+        let exe = std::env::current_exe().unwrap();
+        let missing_version = write_entry(
+            "osu!-profile-gaming.desktop",
+            &format!("{} launch --profile gaming 2023.620.0 -- %U", exe.display()),
+        );
+
+        let paths = super::get_desktop_entry_paths(&root.join("data")).unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(!paths.iter().any(|path| path.ends_with("osu!-stable.desktop")));
+
+        let reason = |path: &Path| super::get_orphan_reason(&install_dir, path).unwrap();
+        assert!(matches!(
+            reason(&missing_binary),
+            Some(super::OrphanReason::MissingProgram(_))
+        ));
+        assert!(reason(&bare_name).is_none());
+        assert!(
+            matches!(reason(&missing_version), Some(super::OrphanReason::MissingVersion(tag)) if tag == "2023.620.0")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
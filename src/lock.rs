@@ -0,0 +1,104 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+use colored::*;
+
+use crate::errors::{self, Error};
+
+/// Advisory lock on the install_dir, held by commands that modify it.
+///
+/// The lock is released when this is dropped, or when the process exits in any way.
+pub struct InstallLock {
+    _file: File,
+}
+
+impl InstallLock {
+    /// Takes the lock, waiting for other instances to release it unless `wait` is false.
+    ///
+    /// The PID of the holder is written into the lock file, so waiting instances can name it.
+    pub fn acquire(install_dir: &Path, wait: bool) -> errors::Result<Self> {
+        fs::create_dir_all(install_dir).map_err(|e| Error::Io {
+            source: e,
+            context: Some(install_dir.to_string_lossy().to_string()),
+        })?;
+
+        let path = install_dir.join(".lock");
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| Error::Io {
+                source: e,
+                context: Some(path.to_string_lossy().to_string()),
+            })?;
+
+        if !try_lock(&file)? {
+            let mut holder = String::new();
+            file.read_to_string(&mut holder)?;
+            let holder = match holder.trim() {
+                "" => "unknown PID".to_owned(),
+                pid => format!("PID {}", pid),
+            };
+
+            if !wait {
+                return Err(Error::Descriptive(format!(
+                    "Another instance ({}) is modifying {}.",
+                    holder,
+                    install_dir.display()
+                )));
+            }
+
+            let message = format!("Waiting for another instance ({}) to finish...", holder);
+            println!("{}", message.yellow());
+            lock(&file)?;
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.sync_all()?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn try_lock(file: &File) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+
+    let error = std::io::Error::last_os_error();
+    if error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn lock(file: &File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+// TODO: Add support for other os alternatives.
+#[cfg(not(target_family = "unix"))]
+fn try_lock(_file: &File) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn lock(_file: &File) -> std::io::Result<()> {
+    Ok(())
+}
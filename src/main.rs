@@ -3,22 +3,34 @@ use std::path::PathBuf;
 use clap::Parser;
 use colored::*;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ConfigAction};
+use config::{Config, InstallMode};
 use errors::Error;
 
+mod appimage;
+mod cleanup;
 mod cli;
 mod commands;
+mod config;
 mod constants;
+mod desktop_entry;
 mod errors;
 mod github;
+mod journal;
 mod local;
+mod lock;
+mod manifest;
 mod net;
+mod sandbox;
 
 fn main() {
     if let Err(e) = run() {
         match e {
             Error::Abort => {}
-            _ => eprintln!("{}", e),
+            _ => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
     };
 }
@@ -40,15 +52,39 @@ fn run() -> errors::Result<()> {
 
     let cli = Cli::parse();
 
+    // These have to work with a broken config, to be able to fix it.
+    if let Commands::Config { action } = cli.command {
+        return match action {
+            ConfigAction::Get { key } => commands::config_get(key),
+            ConfigAction::Set { key, value } => commands::config_set(key, value),
+            ConfigAction::Unset { key } => commands::config_unset(key),
+            ConfigAction::List => commands::config_list(),
+            ConfigAction::Edit => commands::config_edit(),
+            ConfigAction::Path => commands::config_path(),
+        };
+    }
+
+    cleanup::install_interrupt_handler();
+    let mut config = Config::load()?;
+    net::configure(&config.network)?;
+    if let Commands::Install { extract: true, .. } = cli.command {
+        config.install.mode = InstallMode::Extracted;
+    }
+
     let local_data_dir = dirs::data_local_dir().expect("Couldn't find your local data directory.");
-    let install_dir = match cli.install_dir {
+    let install_dir = match cli
+        .install_dir
+        .map(PathBuf::from)
+        .or_else(|| config.install.dir.clone())
+    {
         None => [local_data_dir.to_str().unwrap(), "games", "osu!"].iter().collect(),
-        Some(string) => {
-            let path = PathBuf::from(&string);
+        Some(path) => {
             if !path.try_exists()? {
-                return Err(Error::Descriptive(
-                    "The specified install directory does not exist.".to_owned(),
-                ));
+                std::fs::create_dir_all(&path).map_err(|e| Error::Io {
+                    source: e,
+                    context: Some(path.to_string_lossy().to_string()),
+                })?;
+                println!("Created the install directory at {}", path.display());
             }
 
             if !path.is_dir() {
@@ -61,10 +97,74 @@ fn run() -> errors::Result<()> {
         }
     };
 
+    let _lock = if cli.command.is_mutating() {
+        let lock = lock::InstallLock::acquire(&install_dir, !cli.no_wait)?;
+        commands::recover(&local_data_dir, &install_dir, &config)?;
+        Some(lock)
+    } else {
+        None
+    };
+
     match cli.command {
-        Commands::Install { osu_version } => commands::install(local_data_dir, install_dir, osu_version),
-        Commands::Remove { osu_version } => commands::remove(local_data_dir, install_dir, osu_version),
-        Commands::Update { no_confirm } => commands::update(local_data_dir, install_dir, no_confirm),
+        Commands::Install {
+            osu_version, from_file, ..
+        } => commands::install(local_data_dir, install_dir, &config, osu_version, from_file),
+        Commands::Remove { osu_version } => commands::remove(local_data_dir, install_dir, &config, osu_version),
+        Commands::Config { .. } => unreachable!("The config commands are run before the config is loaded"),
+        Commands::Doctor => commands::doctor(local_data_dir, install_dir, &config),
+        Commands::Identify { file } => commands::identify(file),
+        Commands::Info { osu_version } => commands::info(local_data_dir, install_dir, osu_version),
+        Commands::Isolate { osu_version, seed } => {
+            commands::isolate(local_data_dir, install_dir, &config, osu_version, seed)
+        }
+        Commands::Unisolate {
+            osu_version,
+            delete_data,
+        } => commands::unisolate(local_data_dir, install_dir, &config, osu_version, delete_data),
+        Commands::Launch {
+            osu_version,
+            active,
+            profile,
+            check_updates,
+            no_check_updates,
+            sandbox,
+            print_command,
+            detach,
+            log,
+            args,
+        } => {
+            let options = commands::LaunchOptions {
+                active,
+                profile,
+                check_updates: (check_updates || config.launch.check_updates) && !no_check_updates,
+                sandbox,
+                print_command,
+                detach,
+                log,
+            };
+            commands::launch(local_data_dir, install_dir, &config, osu_version, args, options)
+        }
+        Commands::List { remote } => commands::list(local_data_dir, install_dir, &config, remote),
+        Commands::Pin { osu_version } => commands::pin(install_dir, osu_version, true),
+        Commands::Unpin { osu_version } => commands::pin(install_dir, osu_version, false),
+        Commands::Prune {
+            keep,
+            dry_run,
+            no_confirm,
+        } => {
+            let no_confirm = no_confirm || !config.confirm.prune;
+            commands::prune(local_data_dir, install_dir, &config, keep, dry_run, no_confirm)
+        }
+        Commands::Reinstall { osu_version } => commands::reinstall(local_data_dir, install_dir, osu_version),
+        Commands::Repair { osu_version, all } => {
+            commands::repair(local_data_dir, install_dir, &config, osu_version, all)
+        }
+        Commands::Rollback => commands::rollback(local_data_dir, install_dir, &config),
+        Commands::Use { osu_version } => commands::activate(local_data_dir, install_dir, &config, osu_version),
+        Commands::Update { no_confirm } => {
+            let no_confirm = no_confirm || !config.confirm.update;
+            commands::update(local_data_dir, install_dir, &config, no_confirm)
+        }
     }?;
 
     Ok(())
@@ -0,0 +1,91 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{self, Error};
+use crate::github::Channel;
+
+/// Metadata about the versions installed in an install_dir that can't be derived from the files alone.
+///
+/// Stored as `manifest.json` next to the binaries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub versions: BTreeMap<String, VersionEntry>,
+    /// Versions that are never removed by the retention policy.
+    #[serde(default)]
+    pub pinned: BTreeSet<String>,
+    /// Versions that keep their osu! data in their own directory instead of the main one.
+    #[serde(default)]
+    pub isolated: BTreeSet<String>,
+    /// The most recent update, used to roll back to the version it replaced.
+    #[serde(default)]
+    pub last_update: Option<UpdateRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRecord {
+    pub from: String,
+    pub to: String,
+    /// Copy of `client.realm` taken before the new version got a chance to migrate it.
+    pub realm_snapshot: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionEntry {
+    pub channel: Channel,
+    /// RFC 3339 timestamp of when this version was installed.
+    pub installed_at: String,
+    /// Hex encoded SHA-256 of the binary, as it was written to disk.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl VersionEntry {
+    pub fn new(channel: Channel, sha256: Option<String>) -> Self {
+        Self {
+            channel,
+            installed_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            sha256,
+        }
+    }
+}
+
+impl Manifest {
+    pub fn path(install_dir: &Path) -> PathBuf {
+        install_dir.join("manifest.json")
+    }
+
+    /// Reads the manifest from the install_dir.
+    ///
+    /// Returns an empty manifest if the file doesn't exist, e.g. for versions installed by older releases of this tool.
+    pub fn load(install_dir: &Path) -> errors::Result<Self> {
+        let path = Self::path(install_dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(Error::Io {
+                    source: e,
+                    context: Some(path.to_string_lossy().to_string()),
+                })
+            }
+        };
+
+        serde_json::from_str(&content)
+            .map_err(|e| Error::Descriptive(format!("Couldn't parse the manifest at {}:\n{}", path.display(), e)))
+    }
+
+    pub fn save(&self, install_dir: &Path) -> errors::Result<()> {
+        let path = Self::path(install_dir);
+        let content = serde_json::to_string_pretty(self).expect("manifest should always be serializable");
+
+        fs::write(&path, content).map_err(|e| Error::Io {
+            source: e,
+            context: Some(path.to_string_lossy().to_string()),
+        })
+    }
+}
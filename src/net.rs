@@ -1,12 +1,37 @@
 use indicatif::{ProgressBar, ProgressStyle};
 use std::io::{Read, Write};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use crate::{
+    config::NetworkConfig,
     constants::USER_AGENT,
     errors::{self, Error},
     github::GithubReleaseAsset,
 };
 
+static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+
+/// Sets up the agent every request goes through with the network options from the config.
+pub fn configure(config: &NetworkConfig) -> errors::Result<()> {
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    let mut builder = ureq::AgentBuilder::new().timeout_connect(timeout).timeout_read(timeout);
+    if let Some(proxy) = &config.proxy {
+        let proxy = ureq::Proxy::new(proxy)
+            .map_err(|e| Error::Descriptive(format!("The proxy {} in the config isn't valid:\n{}", proxy, e)))?;
+        builder = builder.proxy(proxy);
+    }
+
+    // Only the first configuration counts, which is the one made on start.
+    let _ = AGENT.set(builder.build());
+    Ok(())
+}
+
+/// Returns the agent set up by [configure], or a default one if it wasn't called.
+pub fn agent() -> ureq::Agent {
+    AGENT.get_or_init(ureq::Agent::new).clone()
+}
+
 pub fn box_request(request: ureq::Request) -> Result<ureq::Response, Box<ureq::Error>> {
     match request.call() {
         Ok(response) => Ok(response),
@@ -28,7 +53,8 @@ pub fn box_and_deserialize<T: for<'a> serde::Deserialize<'a>>(request: ureq::Req
 pub fn download_file_with_progress(
     mut reader: Box<dyn Read + Send + Sync + 'static>,
     size: u64,
-) -> Result<Vec<u8>, std::io::Error> {
+    output: &mut impl Write,
+) -> Result<(), std::io::Error> {
     let pb = ProgressBar::new(size)
         .with_style(ProgressStyle::with_template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
         .unwrap()
@@ -38,7 +64,6 @@ pub fn download_file_with_progress(
 
     // 24MB buffer
     let mut buffer = vec![0; 24 * 1024 * 1024];
-    let mut output_buf = Vec::with_capacity(size as usize);
 
     loop {
         let read_bytes = reader.read(&mut buffer)?;
@@ -46,13 +71,13 @@ pub fn download_file_with_progress(
             break;
         }
 
-        output_buf.write_all(&buffer[..read_bytes])?;
+        output.write_all(&buffer[..read_bytes])?;
         tracker.increment(read_bytes as u64);
     }
 
     tracker.progress_bar.finish();
 
-    Ok(output_buf)
+    Ok(())
 }
 
 pub struct ProgressTracker<'a> {
@@ -78,10 +103,11 @@ impl<'a> ProgressTracker<'a> {
 
 /// Downloads a given release asset with a progress bar.
 ///
-/// Internally, this requests the asset, and then streams the response into a Vec<u8>.
-pub fn download_release_asset(asset: &GithubReleaseAsset) -> errors::Result<Vec<u8>> {
+/// Internally, this requests the asset, and then streams the response into the output.
+pub fn download_release_asset(asset: &GithubReleaseAsset, output: &mut impl Write) -> errors::Result<()> {
     let response = box_request(
-        ureq::get(&asset.browser_download_url)
+        agent()
+            .get(&asset.browser_download_url)
             .set("Accept", "application/octet-stream")
             .set("User-Agent", USER_AGENT),
     )?;
@@ -98,5 +124,9 @@ pub fn download_release_asset(asset: &GithubReleaseAsset) -> errors::Result<Vec<
         )));
     }
 
-    Ok(download_file_with_progress(response.into_reader(), server_size)?)
+    Ok(download_file_with_progress(
+        response.into_reader(),
+        server_size,
+        output,
+    )?)
 }
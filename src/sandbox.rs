@@ -0,0 +1,186 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::SandboxConfig;
+use crate::errors::{self, Error};
+
+/// System directories the game reads its libraries, fonts, certificates and drivers from.
+const SYSTEM_DIRS: &[&str] = &["/usr", "/etc", "/opt", "/bin", "/sbin", "/lib", "/lib32", "/lib64"];
+
+/// Device directories for the GPU and sound.
+const DEVICE_DIRS: &[&str] = &["/dev/dri", "/dev/snd"];
+
+/// Sockets in `XDG_RUNTIME_DIR` for audio servers. Wayland's is taken from `WAYLAND_DISPLAY`.
+const RUNTIME_SOCKETS: &[&str] = &["pulse", "pipewire-0"];
+
+/// Returns the arguments for `bwrap` that run the game at game_path, an AppImage or an extracted directory.
+///
+/// The sandbox sees the system directories read-only, gets the GPU and sound devices, and can only write to the
+/// data_dirs and the binds from the config. The home directory is an empty tmpfs that's gone once the game exits.
+pub fn bwrap_args(config: &SandboxConfig, game_path: &Path, data_dirs: &[PathBuf]) -> errors::Result<Vec<OsString>> {
+    let mut args = Vec::new();
+    push(&mut args, ["--die-with-parent", "--new-session", "--unshare-all"]);
+    if config.share_network {
+        push(&mut args, ["--share-net"]);
+    }
+
+    for dir in SYSTEM_DIRS.iter().map(Path::new) {
+        match dir.symlink_metadata() {
+            // Merged /usr systems link /bin and friends into /usr, which has to be recreated rather than bound.
+            Ok(metadata) if metadata.is_symlink() => {
+                push(
+                    &mut args,
+                    ["--symlink".into(), fs::read_link(dir)?.into_os_string(), dir.into()],
+                );
+            }
+            Ok(_) => bind(&mut args, "--ro-bind", dir, dir),
+            Err(_) => {}
+        }
+    }
+
+    push(
+        &mut args,
+        ["--proc", "/proc", "--dev", "/dev", "--ro-bind-try", "/sys", "/sys"],
+    );
+    // `/etc/resolv.conf` is usually a link into systemd-resolved's runtime directory, which has to come along for DNS.
+    if config.share_network {
+        push(
+            &mut args,
+            ["--ro-bind-try", "/run/systemd/resolve", "/run/systemd/resolve"],
+        );
+    }
+    for dir in DEVICE_DIRS {
+        push(&mut args, ["--dev-bind-try", dir, dir]);
+    }
+
+    // The NVIDIA driver doesn't go through /dev/dri, but has its own device nodes.
+    if let Ok(entries) = fs::read_dir("/dev") {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("nvidia") {
+                let path = entry.path();
+                bind(&mut args, "--dev-bind", &path, &path);
+            }
+        }
+    }
+
+    push(
+        &mut args,
+        ["--tmpfs", "/tmp", "--ro-bind-try", "/tmp/.X11-unix", "/tmp/.X11-unix"],
+    );
+    if let Some(home_dir) = dirs::home_dir() {
+        push(&mut args, ["--tmpfs".into(), home_dir.into_os_string()]);
+    }
+    if let Some(xauthority) = std::env::var_os("XAUTHORITY") {
+        push(&mut args, ["--ro-bind-try".into(), xauthority.clone(), xauthority]);
+    }
+    if let Some(runtime_dir) = dirs::runtime_dir() {
+        let wayland_socket = std::env::var_os("WAYLAND_DISPLAY").unwrap_or_else(|| "wayland-0".into());
+        for socket in RUNTIME_SOCKETS.iter().map(OsString::from).chain([wayland_socket]) {
+            let path = runtime_dir.join(socket);
+            bind(&mut args, "--ro-bind-try", &path, &path);
+        }
+    }
+
+    bind(&mut args, "--ro-bind", game_path, game_path);
+    // FUSE isn't available in the sandbox, so the AppImage has to unpack itself into /tmp instead.
+    if game_path.is_file() {
+        push(&mut args, ["--setenv", "APPIMAGE_EXTRACT_AND_RUN", "1"]);
+    }
+
+    for dir in data_dirs {
+        bind(&mut args, "--bind", dir, dir);
+    }
+    for bind in &config.binds {
+        let (source, destination) = parse_bind(bind)?;
+        push(&mut args, ["--bind".into(), source, destination]);
+    }
+    for bind in &config.ro_binds {
+        let (source, destination) = parse_bind(bind)?;
+        push(&mut args, ["--ro-bind".into(), source, destination]);
+    }
+
+    Ok(args)
+}
+
+fn bind(args: &mut Vec<OsString>, option: &str, source: &Path, destination: &Path) {
+    args.extend([option.into(), source.into(), destination.into()]);
+}
+
+fn push<T: Into<OsString>>(args: &mut Vec<OsString>, items: impl IntoIterator<Item = T>) {
+    args.extend(items.into_iter().map(Into::into));
+}
+
+/// Splits a bind from the config, `path` or `source:destination`, into its source and destination.
+fn parse_bind(bind: &str) -> errors::Result<(OsString, OsString)> {
+    let (source, destination) = bind.split_once(':').unwrap_or((bind, bind));
+    let source = expand_home(source)?;
+    let destination = expand_home(destination)?;
+
+    if !source.is_absolute() || !destination.is_absolute() {
+        return Err(Error::Descriptive(format!(
+            "The sandbox bind \"{}\" has to use absolute paths, or start with ~/.",
+            bind
+        )));
+    }
+
+    Ok((source.into_os_string(), destination.into_os_string()))
+}
+
+fn expand_home(path: &str) -> errors::Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(relative) => match dirs::home_dir() {
+            Some(home_dir) => Ok(home_dir.join(relative)),
+            None => Err(Error::Descriptive("Couldn't find your home directory.".to_owned())),
+        },
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    use super::{bwrap_args, parse_bind};
+    use crate::config::SandboxConfig;
+
+    fn position(args: &[OsString], expected: &[&str]) -> Option<usize> {
+        args.windows(expected.len())
+            .position(|window| window.iter().zip(expected).all(|(arg, expected)| arg == expected))
+    }
+
+    #[test]
+    fn default_binds_are_added() {
+        let data_dir = PathBuf::from("/data/osu");
+        let args = bwrap_args(&SandboxConfig::default(), Path::new("/games/osu.AppImage"), &[data_dir]).unwrap();
+
+        if Path::new("/usr").is_dir() && !Path::new("/usr").is_symlink() {
+            assert!(position(&args, &["--ro-bind", "/usr", "/usr"]).is_some());
+        }
+        assert!(position(&args, &["--dev-bind-try", "/dev/dri", "/dev/dri"]).is_some());
+        assert!(position(
+            &args,
+            &["--ro-bind-try", "/run/systemd/resolve", "/run/systemd/resolve"]
+        )
+        .is_some());
+        assert!(position(&args, &["--ro-bind", "/games/osu.AppImage", "/games/osu.AppImage"]).is_some());
+
+        // The home directory is hidden first, so the binds within it that come later stay visible.
+        let data_bind = position(&args, &["--bind", "/data/osu", "/data/osu"]).unwrap();
+        if let Some(home_dir) = dirs::home_dir() {
+            let home_dir = home_dir.to_string_lossy().to_string();
+            assert!(position(&args, &["--tmpfs", &home_dir]).unwrap() < data_bind);
+        }
+    }
+
+    #[test]
+    fn binds_are_parsed() {
+        assert_eq!(
+            parse_bind("/mnt/songs").unwrap(),
+            ("/mnt/songs".into(), "/mnt/songs".into())
+        );
+        assert_eq!(parse_bind("/a:/b").unwrap(), ("/a".into(), "/b".into()));
+        assert!(parse_bind("relative").is_err());
+    }
+}